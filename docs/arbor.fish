@@ -1,11 +1,52 @@
 complete -c arbor -s G -l show-gitignored -d "Show files listed in .gitignore"
 complete -c arbor -s H -l show-hiddens -d "Include hidden files and directories"
+complete -c arbor -s x -l one-file-system -d "Stay on the root's filesystem"
 complete -c arbor -s c -l color -a "auto always never" -d "Color output mode"
+complete -c arbor -l color-depth -a "auto truecolor 256 16 none" -d "Colors the terminal can show"
 complete -c arbor -s i -l icons -d "Add icons for known file types"
+complete -c arbor -l theme -r -F -a "default mono nord gruvbox" -d "Built-in theme or TOML theme file"
+complete -c arbor -l charset -a "utf8 ascii rounded heavy dashed" -d "Characters of the tree guides"
+complete -c arbor -l indent -r -d "Columns per tree level (2 to 8)"
+complete -c arbor -l no-guides -d "Indent with spaces only"
+complete -c arbor -l fromfile -r -d "Build the tree from a path list (- for stdin)"
+complete -c arbor -l stat -d "Stat the paths given with --fromfile"
+complete -c arbor -l tracked -d "Only files in the Git index"
+complete -c arbor -l untracked -d "Add untracked files with --tracked"
+complete -c arbor -s A -l archives -d "Show archive contents as directories"
+complete -c arbor -l newer-than -d "Only files modified after a duration, date or file"
+complete -c arbor -l older-than -d "Only files modified before a duration, date or file"
+complete -c arbor -l min-size -d "Only files of at least this size"
+complete -c arbor -l max-size -d "Only files of at most this size"
+complete -c arbor -l type -x -a "(arbor --type-list | string replace -r ':.*' '')" -d "Only files of this type"
+complete -c arbor -l type-not -x -a "(arbor --type-list | string replace -r ':.*' '')" -d "Hide files of this type"
+complete -c arbor -l type-add -d "Define a file type (name:glob)"
+complete -c arbor -l type-list -d "List known file types"
+complete -c arbor -l grep -x -d "Only files whose contents match a regex"
+complete -c arbor -l grep-lines -d "Print matching lines with --grep"
+complete -c arbor -s f -l find -x -d "Only entries whose name matches a regex or glob"
+complete -c arbor -l find-context -x -d "Siblings shown around each --find match"
+complete -c arbor -l duplicates -d "Only show files with identical copies"
+complete -c arbor -s Q -l quoting-style -a "literal escape shell c" -d "File name quoting style"
 complete -c arbor -s d -l depth -d "Limit tree depth"
+complete -c arbor -l min-depth -d "Hide entries shallower than N"
+complete -c arbor -l depth-hint -d "Show entry counts below the depth limit"
+complete -c arbor -l focus -r -d "Expand only the path to this file"
+complete -c arbor -l compact -d "Merge single-child directory chains"
+complete -c arbor -l compact-dirs-only -d "Only merge directories with --compact"
+complete -c arbor -s s -l sort -a "name size kind" -d "Sort entries"
+complete -c arbor -l max-entries-per-dir -d "Limit entries shown per directory"
 complete -c arbor -s g -l git -d "Display Git status indicators"
 complete -c arbor -s b -l git-branch -d "Show Git branch name"
 complete -c arbor -s j -l json -d "Output the tree as JSON"
 complete -c arbor -s n -l count -d "Print only the number of files"
+complete -c arbor -l loc -d "Count code, comment and blank lines"
+complete -c arbor -l hash -x -a "blake3 sha256" -d "Show file content digests"
+complete -c arbor -l manifest -d "Print a sha256sum-compatible manifest"
+complete -c arbor -l verify -r -d "Compare files with a checksum manifest"
+complete -c arbor -s w -l watch -d "Redraw the tree when files change"
+complete -c arbor -l cache -d "Reuse unchanged results of the previous run"
+complete -c arbor -l no-cache -d "Walk everything again, ignoring --cache"
 complete -c arbor -s t -l time -d "Measure execution time"
 complete -c arbor -s v -l verbose -d "Enable verbose logging"
+complete -c arbor -n "__fish_use_subcommand" -a diff -d "Compare two directories or JSON snapshots"
+complete -c arbor -n "__fish_seen_subcommand_from diff" -l changed-only -d "Hide unchanged entries"
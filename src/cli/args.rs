@@ -3,18 +3,93 @@
 
 use crate::config;
 use crate::version;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
-#[derive(Copy, Clone, Debug, ValueEnum, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
 pub enum ColorMode {
+    #[default]
     Auto,
     Always,
     Never,
 }
 
+/// Colors the terminal can show, for `--color-depth`.
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
+pub enum ColorDepth {
+    /// Detect from COLORTERM and TERM
+    #[default]
+    Auto,
+    /// 24-bit colors
+    Truecolor,
+    /// The 256-color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// The 16 basic colors
+    #[value(name = "16")]
+    Ansi16,
+    /// No colors at all
+    None,
+}
+
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Size,
+    Kind,
+}
+
+/// How file names are written, following GNU `ls --quoting-style`.
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
+pub enum QuotingStyle {
+    /// Raw bytes, no escaping (unsafe on a terminal)
+    Literal,
+    /// Backslash escapes for control characters and invalid bytes
+    #[default]
+    Escape,
+    /// Quote for POSIX shells when needed
+    Shell,
+    /// Double-quoted C string
+    C,
+}
+
+/// Characters of the tree guides, for `--charset`.
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
+pub enum Charset {
+    /// ├── └── │
+    #[default]
+    Utf8,
+    /// |-- `-- |
+    Ascii,
+    /// ├── ╰── │
+    Rounded,
+    /// ┣━━ ┗━━ ┃
+    Heavy,
+    /// ├╌╌ └╌╌ ┆
+    Dashed,
+}
+
+/// Content digest computed by `--hash`.
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq)]
+pub enum HashAlgo {
+    Blake3,
+    #[default]
+    Sha256,
+}
+
+impl HashAlgo {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgo::Blake3 => "blake3",
+            HashAlgo::Sha256 => "sha256",
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(
     name = "arbor",
+    disable_help_subcommand = true,
     version = version::SHORT,
     long_version = version::LONG,
     about = " Arbor — a modern and smart reimplementation of the classic `tree` command.",
@@ -28,6 +103,9 @@ navigate your projects more clearly and efficiently."#,
 USAGE:
   {usage}
 
+COMMANDS:
+{subcommands}
+
 OPTIONS:
 {options}
 {after-help}"#
@@ -37,28 +115,161 @@ pub struct Args {
     // BASIC OPTIONS
     // -----------
     /// Show files that are listed in .gitignore (ignored by default)
-    #[arg(long, short = 'G')]
+    #[arg(long, short = 'G', global = true)]
     pub show_gitignored: bool,
 
     /// Include hidden files and directories (starting with .)
-    #[arg(long, short = 'H')]
+    #[arg(long, short = 'H', global = true)]
     pub show_hiddens: bool,
 
+    /// Do not descend into directories on other filesystems (mount points are still shown)
+    #[arg(long, short = 'x')]
+    pub one_file_system: bool,
+
     /// Colorize the output (Auto, Always, Never).
-    #[arg(long, short = 'c', value_enum, default_value_t = ColorMode::Auto)]
+    #[arg(long, short = 'c', value_enum, default_value_t = ColorMode::Auto, global = true)]
     pub color: ColorMode,
 
+    /// Colors the terminal can show, theme colors being mapped to the
+    /// closest ones
+    #[arg(long, value_enum, default_value_t = ColorDepth::Auto, global = true)]
+    pub color_depth: ColorDepth,
+
     /// Add icons for known file types and directories
-    #[arg(long, short = 'i')]
+    #[arg(long, short = 'i', global = true)]
     pub icons: bool,
 
+    /// Colors, icons and Git markers: a built-in theme (default, mono, nord,
+    /// gruvbox) or a TOML theme file
+    #[arg(long, value_name = "NAME|FILE", global = true)]
+    pub theme: Option<String>,
+
+    /// How to write file names containing special characters
+    #[arg(long, short = 'Q', value_enum, default_value_t = QuotingStyle::Escape, global = true)]
+    pub quoting_style: QuotingStyle,
+
+    /// Characters of the tree guides
+    #[arg(long, value_enum, default_value_t = Charset::Utf8, global = true)]
+    pub charset: Charset,
+
+    /// Columns per tree level, guides included (2 to 8)
+    #[arg(long, value_name = "N", default_value_t = 4, global = true)]
+    pub indent: usize,
+
+    /// Indent entries with spaces only, without guide lines
+    #[arg(long, global = true)]
+    pub no_guides: bool,
+
+    /// Build the tree from the paths listed in FILE (or stdin with -), one per line or NUL-separated
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "-")]
+    pub fromfile: Option<String>,
+
+    /// With --fromfile, read kinds and sizes from the filesystem
+    #[arg(long, requires = "fromfile")]
+    pub stat: bool,
+
+    /// Build the tree from the Git index: only tracked files are shown
+    #[arg(long, conflicts_with = "fromfile")]
+    pub tracked: bool,
+
+    /// With --tracked, also show untracked files that are not ignored
+    #[arg(long, requires = "tracked")]
+    pub untracked: bool,
+
+    /// Show the contents of .tar, .tar.gz, .tgz, .crate and .zip archives as directories
+    #[arg(long, short = 'A')]
+    pub archives: bool,
+
+    // ------------
+    // FILTERS
+    // ------------
+    /// Only show files modified after a duration ago (2h), a date (2025-11-20) or a file's mtime
+    #[arg(long, value_name = "WHEN")]
+    pub newer_than: Option<String>,
+
+    /// Only show files modified before a duration ago (2h), a date (2025-11-20) or a file's mtime
+    #[arg(long, value_name = "WHEN")]
+    pub older_than: Option<String>,
+
+    /// Only show files of at least this size (e.g. 50M, 1.5GiB)
+    #[arg(long, value_name = "SIZE")]
+    pub min_size: Option<String>,
+
+    /// Only show files of at most this size (e.g. 50M, 1.5GiB)
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<String>,
+
+    /// Only show files of this type (e.g. rust, py); see --type-list
+    #[arg(long = "type", value_name = "TYPE", global = true)]
+    pub types: Vec<String>,
+
+    /// Hide files of this type
+    #[arg(long, value_name = "TYPE", global = true)]
+    pub type_not: Vec<String>,
+
+    /// Define a file type, as name:glob (e.g. 'proto:*.proto')
+    #[arg(long, value_name = "NAME:GLOB", global = true)]
+    pub type_add: Vec<String>,
+
+    /// List the known file types and exit
+    #[arg(long)]
+    pub type_list: bool,
+
+    /// Only show files whose contents match this regex, with their match count
+    #[arg(long, value_name = "REGEX")]
+    pub grep: Option<String>,
+
+    /// With --grep, print the matching lines under each file
+    #[arg(long, requires = "grep")]
+    pub grep_lines: bool,
+
+    /// Only show entries whose name matches this regex or glob, with their parents
+    #[arg(long, short = 'f', value_name = "PATTERN")]
+    pub find: Option<String>,
+
+    /// With --find, also show N siblings on each side of every match
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "find")]
+    pub find_context: usize,
+
+    /// Only show files whose contents are found more than once, with the paths of their copies
+    #[arg(long, conflicts_with_all = ["manifest", "verify", "count"])]
+    pub duplicates: bool,
+
     // ------------
     // TREE RENDERING
     // ------------
     /// Limit the displayed depth of the tree
-    #[arg(long, short = 'd', value_name = "N")]
+    #[arg(long, short = 'd', value_name = "N", global = true)]
     pub depth: Option<usize>,
 
+    /// Hide entries shallower than N (their directories are kept as ancestors)
+    #[arg(long, value_name = "N")]
+    pub min_depth: Option<usize>,
+
+    /// Show how many entries are hidden below directories cut off by --depth
+    #[arg(long)]
+    pub depth_hint: bool,
+
+    /// Expand only the directories leading to PATH (repeatable); other entries are shown down to --depth
+    #[arg(long, value_name = "PATH")]
+    pub focus: Vec<String>,
+
+    /// Merge chains of single-child directories into one line (a/b/c/)
+    #[arg(long)]
+    pub compact: bool,
+
+    /// With --compact, never merge a directory into its only file
+    #[arg(long, requires = "compact")]
+    pub compact_dirs_only: bool,
+
+    /// Order entries by name, size (largest first) or kind (directories first)
+    #[arg(long, short = 's', value_enum, default_value_t = SortKey::Name, global = true)]
+    pub sort: SortKey,
+
+    /// Show at most N entries per directory, followed by a summary of the rest
+    #[arg(long, value_name = "N")]
+    pub max_entries_per_dir: Option<usize>,
+
     // -------------------
     // GIT INTEGRATION
     // -------------------
@@ -74,13 +285,41 @@ pub struct Args {
     // OUTPUT CONTROL
     // -------------------
     /// Output the tree as a JSON structure
-    #[arg(long, short = 'j')]
+    #[arg(long, short = 'j', global = true)]
     pub json: bool,
 
     /// Print only the number of files and directories
-    #[arg(long, short = 'n')]
+    #[arg(long, short = 'n', global = true)]
     pub count: bool,
 
+    /// Count code, comment and blank lines per file and directory; with --count, per language
+    #[arg(long)]
+    pub loc: bool,
+
+    /// Show a content digest of every file
+    #[arg(long, value_enum, value_name = "ALGO", global = true)]
+    pub hash: Option<HashAlgo>,
+
+    /// Print a sha256sum-compatible manifest of the shown files
+    #[arg(long, conflicts_with_all = ["json", "count"])]
+    pub manifest: bool,
+
+    /// Compare the files with a checksum manifest and mark them ok, changed, missing or new
+    #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["manifest", "count"])]
+    pub verify: Option<String>,
+
+    /// Redraw the tree whenever files change, highlighting what changed
+    #[arg(long, short = 'w', conflicts_with_all = ["json", "count", "manifest", "verify", "fromfile", "tracked", "cache"])]
+    pub watch: bool,
+
+    /// Reuse the listings, digests and line counts of unchanged directories and files from the previous run
+    #[arg(long, overrides_with = "no_cache")]
+    pub cache: bool,
+
+    /// Walk everything again, ignoring --cache
+    #[arg(long, overrides_with = "cache")]
+    pub no_cache: bool,
+
     /// Measure and display execution time
     #[arg(long, short = 't')]
     pub time: bool,
@@ -92,9 +331,31 @@ pub struct Args {
     #[arg(long, short = 'v')]
     pub verbose: bool,
 
-    /// Root directory to explore (default: current directory)
-    #[arg(default_value = ".")]
-    pub root: String,
+    /// Root directories to explore, one tree each (default: current directory)
+    #[arg(default_value = ".", value_name = "ROOT")]
+    pub roots: Vec<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compare two directories or JSON snapshots (arbor --json > snap.json)
+    Diff(DiffArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Old side: a directory or a JSON snapshot
+    pub old: String,
+
+    /// New side: a directory or a JSON snapshot
+    pub new: String,
+
+    /// Hide unchanged entries
+    #[arg(long)]
+    pub changed_only: bool,
 }
 
 impl Args {
@@ -111,12 +372,41 @@ mod tests {
     #[test]
     fn defaults_are_correct() {
         let args = Args::try_parse_from(["arbor"]).unwrap();
-        assert_eq!(args.root, ".");
+        assert_eq!(args.roots, ["."]);
         assert!(!args.show_gitignored);
         assert!(!args.show_hiddens);
+        assert!(!args.one_file_system);
+        assert!(args.newer_than.is_none());
+        assert!(args.older_than.is_none());
+        assert!(args.min_size.is_none());
+        assert!(args.max_size.is_none());
+        assert!(args.types.is_empty());
+        assert!(args.type_not.is_empty());
+        assert!(args.type_add.is_empty());
+        assert!(!args.type_list);
+        assert!(args.grep.is_none());
+        assert!(!args.grep_lines);
+        assert!(args.find.is_none());
+        assert_eq!(args.find_context, 0);
         assert_eq!(args.color, ColorMode::Auto);
         assert!(!args.icons);
+        assert_eq!(args.quoting_style, QuotingStyle::Escape);
+        assert_eq!(args.charset, Charset::Utf8);
+        assert_eq!(args.indent, 4);
+        assert!(!args.no_guides);
+        assert!(args.fromfile.is_none());
+        assert!(!args.stat);
+        assert!(!args.tracked);
+        assert!(!args.untracked);
+        assert!(!args.archives);
         assert!(args.depth.is_none());
+        assert!(args.min_depth.is_none());
+        assert!(!args.depth_hint);
+        assert!(args.focus.is_empty());
+        assert!(!args.compact);
+        assert!(!args.compact_dirs_only);
+        assert_eq!(args.sort, SortKey::Name);
+        assert!(args.max_entries_per_dir.is_none());
         assert!(!args.git);
         assert!(!args.git_branch);
         assert!(!args.json);
@@ -128,7 +418,13 @@ mod tests {
     #[test]
     fn root_positional_is_parsed() {
         let args = Args::try_parse_from(["arbor", "/home/vatara"]).unwrap();
-        assert_eq!(args.root, "/home/vatara");
+        assert_eq!(args.roots, ["/home/vatara"]);
+    }
+
+    #[test]
+    fn several_roots_are_parsed() {
+        let args = Args::try_parse_from(["arbor", "src", "tests", "docs"]).unwrap();
+        assert_eq!(args.roots, ["src", "tests", "docs"]);
     }
 
     #[test]
@@ -143,6 +439,65 @@ mod tests {
         assert!(args.show_hiddens);
     }
 
+    #[test]
+    fn one_file_system_flag() {
+        let args = Args::try_parse_from(["arbor", "-x"]).unwrap();
+        assert!(args.one_file_system);
+    }
+
+    #[test]
+    fn type_options_can_be_repeated() {
+        let args = Args::try_parse_from([
+            "arbor",
+            "--type",
+            "rust",
+            "--type",
+            "toml",
+            "--type-not",
+            "markdown",
+            "--type-add",
+            "proto:*.proto",
+        ])
+        .unwrap();
+        assert_eq!(args.types, vec!["rust", "toml"]);
+        assert_eq!(args.type_not, vec!["markdown"]);
+        assert_eq!(args.type_add, vec!["proto:*.proto"]);
+    }
+
+    #[test]
+    fn grep_lines_requires_grep() {
+        let args = Args::try_parse_from(["arbor", "--grep", "TODO", "--grep-lines"]).unwrap();
+        assert_eq!(args.grep.as_deref(), Some("TODO"));
+        assert!(args.grep_lines);
+        assert!(Args::try_parse_from(["arbor", "--grep-lines"]).is_err());
+    }
+
+    #[test]
+    fn fromfile_defaults_to_stdin() {
+        let args = Args::try_parse_from(["arbor", "--fromfile"]).unwrap();
+        assert_eq!(args.fromfile.as_deref(), Some("-"));
+        let args = Args::try_parse_from(["arbor", "--fromfile=list.txt", "--stat"]).unwrap();
+        assert_eq!(args.fromfile.as_deref(), Some("list.txt"));
+        assert!(args.stat);
+        assert!(Args::try_parse_from(["arbor", "--stat"]).is_err());
+    }
+
+    #[test]
+    fn tracked_flags() {
+        let args = Args::try_parse_from(["arbor", "--tracked", "--untracked"]).unwrap();
+        assert!(args.tracked && args.untracked);
+        assert!(Args::try_parse_from(["arbor", "--untracked"]).is_err());
+        assert!(Args::try_parse_from(["arbor", "--tracked", "--fromfile"]).is_err());
+    }
+
+    #[test]
+    fn find_context_requires_find() {
+        let args = Args::try_parse_from(["arbor", "-f", "*.rs", "--find-context", "2"]).unwrap();
+        assert_eq!(args.find.as_deref(), Some("*.rs"));
+        assert_eq!(args.find_context, 2);
+        assert!(Args::try_parse_from(["arbor", "--find-context", "1"]).is_err());
+    }
+
     #[test]
     fn color_variants_parse() {
         let a = Args::try_parse_from(["arbor", "--color", "auto"]).unwrap();
@@ -161,12 +516,51 @@ mod tests {
         assert!(args.icons);
     }
 
+    #[test]
+    fn quoting_style_variants_parse() {
+        for (value, expected) in [
+            ("literal", QuotingStyle::Literal),
+            ("escape", QuotingStyle::Escape),
+            ("shell", QuotingStyle::Shell),
+            ("c", QuotingStyle::C),
+        ] {
+            let args = Args::try_parse_from(["arbor", "--quoting-style", value]).unwrap();
+            assert_eq!(args.quoting_style, expected);
+        }
+    }
+
     #[test]
     fn depth_value_is_parsed() {
         let args = Args::try_parse_from(["arbor", "--depth", "3"]).unwrap();
         assert_eq!(args.depth, Some(3));
     }
 
+    #[test]
+    fn min_depth_and_hint_are_parsed() {
+        let args =
+            Args::try_parse_from(["arbor", "--min-depth", "2", "-d", "4", "--depth-hint"]).unwrap();
+        assert_eq!(args.min_depth, Some(2));
+        assert_eq!(args.depth, Some(4));
+        assert!(args.depth_hint);
+    }
+
+    #[test]
+    fn compact_flags() {
+        let args = Args::try_parse_from(["arbor", "--compact", "--compact-dirs-only"]).unwrap();
+        assert!(args.compact);
+        assert!(args.compact_dirs_only);
+
+        assert!(Args::try_parse_from(["arbor", "--compact-dirs-only"]).is_err());
+    }
+
+    #[test]
+    fn sort_and_max_entries_are_parsed() {
+        let args = Args::try_parse_from(["arbor", "--sort", "size", "--max-entries-per-dir", "20"])
+            .unwrap();
+        assert_eq!(args.sort, SortKey::Size);
+        assert_eq!(args.max_entries_per_dir, Some(20));
+    }
+
     #[test]
     fn git_flag() {
         let args = Args::try_parse_from(["arbor", "--git"]).unwrap();
@@ -191,6 +585,48 @@ mod tests {
         assert!(args.count);
     }
 
+    #[test]
+    fn loc_flag() {
+        let args = Args::try_parse_from(["arbor", "--loc", "--count"]).unwrap();
+        assert!(args.loc);
+        assert!(args.count);
+    }
+
+    #[test]
+    fn hash_manifest_and_verify_flags() {
+        let args = Args::try_parse_from(["arbor", "--hash", "blake3"]).unwrap();
+        assert_eq!(args.hash, Some(HashAlgo::Blake3));
+        let args = Args::try_parse_from(["arbor", "--verify", "SHA256SUMS"]).unwrap();
+        assert_eq!(args.verify.as_deref(), Some("SHA256SUMS"));
+        assert!(Args::try_parse_from(["arbor", "--manifest", "--json"]).is_err());
+        assert!(Args::try_parse_from(["arbor", "--manifest", "--verify", "m"]).is_err());
+    }
+
+    #[test]
+    fn diff_subcommand_takes_the_common_options() {
+        let args =
+            Args::try_parse_from(["arbor", "diff", "a", "snap.json", "--json", "-d", "2"]).unwrap();
+        let Some(Command::Diff(d)) = &args.command else {
+            panic!("no diff subcommand");
+        };
+        assert_eq!((d.old.as_str(), d.new.as_str()), ("a", "snap.json"));
+        assert!(args.json);
+        assert_eq!(args.depth, Some(2));
+
+        let args = Args::try_parse_from(["arbor", "src", "docs"]).unwrap();
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn last_cache_flag_wins() {
+        let args = Args::try_parse_from(["arbor", "--cache"]).unwrap();
+        assert!(args.cache);
+        let args = Args::try_parse_from(["arbor", "--cache", "--no-cache"]).unwrap();
+        assert!(!args.cache);
+        let args = Args::try_parse_from(["arbor", "--no-cache", "--cache"]).unwrap();
+        assert!(args.cache);
+    }
+
     #[test]
     fn time_flag() {
         let args = Args::try_parse_from(["arbor", "--time"]).unwrap();
@@ -231,7 +667,7 @@ mod tests {
         assert!(args.git_branch);
         assert!(args.time);
         assert!(args.verbose);
-        assert_eq!(args.root, "root_dir");
+        assert_eq!(args.roots, ["root_dir"]);
     }
 
     #[test]
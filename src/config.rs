@@ -1,29 +1,116 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use std::path::PathBuf;
+use chrono::{Local, NaiveDate, TimeZone};
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
 
-use crate::cli::args::{Args, ColorMode};
+use crate::cli::args::{Args, Command, HashAlgo, QuotingStyle, SortKey};
+use crate::fs_scan::{
+    archive,
+    grep::GrepOptions,
+    types::{self, TypeFilter},
+};
+use crate::helpers;
+use crate::renderer::{
+    colors,
+    depth::{self, Depth},
+    guides::Guides,
+    theme::Theme,
+};
+use crate::transform::find::{self, FindOptions};
 
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Tree,
     Json,
     Count,
+    TypeList,
+    /// `sha256sum`-style list of digests and paths.
+    Manifest,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct WalkOptions {
     pub follow_gitignore: bool,
     pub include_hidden: bool,
     pub depth: Option<usize>,
+    pub min_depth: Option<usize>,
+    pub depth_hint: bool,
+    pub one_file_system: bool,
+    /// Only keep files modified after this instant.
+    pub newer_than: Option<SystemTime>,
+    /// Only keep files modified before this instant.
+    pub older_than: Option<SystemTime>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub types: TypeFilter,
+    /// Only keep files whose contents match.
+    pub grep: Option<GrepOptions>,
+    /// `--focus` targets, relative to the walked root. Left empty in
+    /// `AppConfig::walk`, as each root has its own: see `AppConfig::walk_for`.
+    pub focus: Vec<PathBuf>,
+    /// Path list to build the tree from instead of walking (`-` for stdin).
+    pub fromfile: Option<PathBuf>,
+    /// With `fromfile`, stat the listed paths.
+    pub stat: bool,
+    /// Build the tree from the Git index instead of walking.
+    pub tracked: bool,
+    /// With `tracked`, add untracked files that are not ignored.
+    pub untracked: bool,
+    /// Show the contents of archives found during the walk.
+    pub archives: bool,
+    /// Compute a content digest of every shown file.
+    pub hash: Option<HashAlgo>,
+    /// Only keep files whose contents are found more than once.
+    pub duplicates: bool,
+    /// Count code, comment and blank lines of files of a known language.
+    pub loc: bool,
 }
 
-#[derive(Debug, Clone)]
+impl WalkOptions {
+    /// Whether files are filtered by type, size, modification time or
+    /// content, in which case directories left without any matching file are
+    /// pruned.
+    pub fn has_file_filters(&self) -> bool {
+        self.types.is_active()
+            || self.grep.is_some()
+            || self.newer_than.is_some()
+            || self.older_than.is_some()
+            || self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.duplicates
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct RenderOptions {
-    pub color: ColorMode,
+    /// Color names, Git markers and highlights, as decided by `--color`.
+    pub color: bool,
+    /// Colors the terminal can show, from `--color-depth`.
+    pub depth: Depth,
+    /// Colors, icons and Git markers, from `--theme` or the theme file.
+    pub theme: Theme,
     pub icons: bool,
     pub git: bool,
+    pub quoting: QuotingStyle,
+    /// Tree guides, from `--charset`, `--indent` and `--no-guides`.
+    pub guides: Guides,
+    /// Show the `--loc` columns.
+    pub loc: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransformOptions {
+    pub compact: bool,
+    pub compact_dirs_only: bool,
+    pub sort: SortKey,
+    pub max_entries: Option<usize>,
+    /// Only keep entries whose name matches, with their ancestors.
+    pub find: Option<FindOptions>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,17 +119,37 @@ pub struct GitOptions {
     pub show_branch: bool,
 }
 
+/// The two sides compared by `arbor diff`.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub changed_only: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeOptions {
     pub measure_time: bool,
     pub verbose: bool,
-    pub root: PathBuf,
+    /// Walked and rendered one after another.
+    pub roots: Vec<PathBuf>,
+    /// Checksum manifest the files are compared with.
+    pub verify: Option<PathBuf>,
+    /// Keep running and redraw the tree on changes.
+    pub watch: bool,
+    /// Reuse and update the scan cache of each walked root.
+    pub cache: bool,
+    /// Compare two trees instead of showing the roots.
+    pub diff: Option<DiffOptions>,
+    /// `--focus` targets found in each root, in the order of `roots`.
+    pub focus: Vec<Vec<PathBuf>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub walk: WalkOptions,
     pub render: RenderOptions,
+    pub transform: TransformOptions,
     pub git: GitOptions,
     pub output: OutputFormat,
     pub runtime: RuntimeOptions,
@@ -58,25 +165,208 @@ impl AppConfig {
         {
             return Err(String::from("--depth must be >= 1 when provided"));
         }
+        if let (Some(min), Some(max)) = (raw.min_depth, raw.depth)
+            && min > max
+        {
+            return Err(String::from("--min-depth must not be greater than --depth"));
+        }
+        if raw.max_entries_per_dir == Some(0) {
+            return Err(String::from(
+                "--max-entries-per-dir must be >= 1 when provided",
+            ));
+        }
+        if raw.depth_hint && raw.depth.is_none() {
+            return Err(String::from("--depth-hint requires --depth"));
+        }
+        if !(2..=8).contains(&raw.indent) {
+            return Err(String::from("--indent must be between 2 and 8"));
+        }
+
+        let now = SystemTime::now();
+        let newer_than = raw
+            .newer_than
+            .as_deref()
+            .map(|v| parse_time_spec("--newer-than", v, now))
+            .transpose()?;
+        let older_than = raw
+            .older_than
+            .as_deref()
+            .map(|v| parse_time_spec("--older-than", v, now))
+            .transpose()?;
+        let min_size = raw
+            .min_size
+            .as_deref()
+            .map(|v| helpers::parse_size(v).map_err(|e| format!("--min-size: {e}")))
+            .transpose()?;
+        let max_size = raw
+            .max_size
+            .as_deref()
+            .map(|v| helpers::parse_size(v).map_err(|e| format!("--max-size: {e}")))
+            .transpose()?;
+        if let (Some(min), Some(max)) = (min_size, max_size)
+            && min > max
+        {
+            return Err(String::from(
+                "--min-size must not be greater than --max-size",
+            ));
+        }
+
+        let grep = raw
+            .grep
+            .as_deref()
+            .map(|pattern| {
+                regex::bytes::Regex::new(pattern)
+                    .map(|regex| GrepOptions {
+                        regex,
+                        lines: raw.grep_lines,
+                    })
+                    .map_err(|e| format!("--grep: {e}"))
+            })
+            .transpose()?;
+
+        let find = raw
+            .find
+            .as_deref()
+            .map(|pattern| {
+                find::compile_pattern(pattern)
+                    .map(|regex| FindOptions {
+                        regex,
+                        context: raw.find_context,
+                    })
+                    .map_err(|e| format!("--find: {e}"))
+            })
+            .transpose()?;
+
+        let roots: Vec<PathBuf> = raw.roots.iter().map(PathBuf::from).collect();
+        if raw.fromfile.is_some() && roots.len() > 1 {
+            return Err(String::from("--fromfile takes at most one root"));
+        }
+        if raw.fromfile.is_some()
+            && let Some(flag) = walk_only_option(&raw)
+        {
+            return Err(format!(
+                "--fromfile reads a path list without walking it; {flag} does not apply"
+            ));
+        }
+        let archive_root = roots
+            .iter()
+            .find(|r| r.is_file() && r.file_name().is_some_and(archive::is_archive));
+        if let Some(root) = archive_root
+            && (raw.hash.is_some() || raw.manifest || raw.verify.is_some())
+        {
+            return Err(format!(
+                "{}: the files of archives are listed, not read; --hash, --manifest and --verify do not apply",
+                root.display()
+            ));
+        }
+        if raw.tracked
+            && let Some(flag) = walk_only_option(&raw)
+        {
+            return Err(format!(
+                "--tracked reads the Git index without walking it; {flag} does not apply"
+            ));
+        }
+        if raw.watch && roots.len() > 1 {
+            return Err(String::from("--watch takes at most one root"));
+        }
+        let diff = match raw.command {
+            Some(Command::Diff(d)) => {
+                if raw.watch || raw.manifest || raw.verify.is_some() {
+                    return Err(String::from(
+                        "diff cannot be combined with --watch, --manifest or --verify",
+                    ));
+                }
+                if raw.fromfile.is_some() || raw.tracked {
+                    return Err(String::from(
+                        "diff walks directories; --fromfile and --tracked do not apply",
+                    ));
+                }
+                Some(DiffOptions {
+                    old: PathBuf::from(d.old),
+                    new: PathBuf::from(d.new),
+                    changed_only: d.changed_only,
+                })
+            }
+            None => None,
+        };
+        let focus = focus_paths(&roots, &raw.focus)?;
+
+        let type_filter = TypeFilter {
+            add: raw.type_add,
+            select: raw.types,
+            negate: raw.type_not,
+        };
+        types::build_types(&type_filter)?;
 
-        let output = if raw.json {
+        // A manifest or a verification needs digests; sha256sum's unless
+        // another algorithm is asked for.
+        let hash = raw
+            .hash
+            .or((raw.manifest || raw.verify.is_some()).then_some(HashAlgo::Sha256));
+
+        let output = if raw.type_list {
+            OutputFormat::TypeList
+        } else if raw.json {
             OutputFormat::Json
         } else if raw.count {
             OutputFormat::Count
+        } else if raw.manifest {
+            OutputFormat::Manifest
         } else {
             OutputFormat::Tree
         };
+        let depth = depth::resolve(raw.color_depth);
+        let color = depth.is_some() && colors::enabled(raw.color);
+        let depth = depth.unwrap_or_default();
+        // Only the tree shows what a theme sets, so a broken theme file does
+        // not stop the other outputs.
+        let themed = matches!(output, OutputFormat::Tree) && (color || raw.icons || raw.git);
+        let theme = if themed {
+            Theme::load(raw.theme.as_deref(), color, depth)?
+        } else {
+            Theme::default()
+        };
 
         Ok(Self {
             walk: WalkOptions {
                 follow_gitignore: !raw.show_gitignored,
                 include_hidden: raw.show_hiddens,
                 depth: raw.depth,
+                min_depth: raw.min_depth,
+                depth_hint: raw.depth_hint,
+                one_file_system: raw.one_file_system,
+                newer_than,
+                older_than,
+                min_size,
+                max_size,
+                types: type_filter,
+                grep,
+                focus: Vec::new(),
+                fromfile: raw.fromfile.map(PathBuf::from),
+                stat: raw.stat,
+                tracked: raw.tracked,
+                untracked: raw.untracked,
+                archives: raw.archives,
+                hash,
+                duplicates: raw.duplicates,
+                loc: raw.loc,
             },
             render: RenderOptions {
-                color: raw.color,
+                color,
+                depth,
+                theme,
                 icons: raw.icons,
                 git: raw.git,
+                quoting: raw.quoting_style,
+                guides: Guides::new(raw.charset, raw.indent, !raw.no_guides),
+                loc: raw.loc,
+            },
+            transform: TransformOptions {
+                compact: raw.compact,
+                compact_dirs_only: raw.compact_dirs_only,
+                sort: raw.sort,
+                max_entries: raw.max_entries_per_dir,
+                find,
             },
             git: GitOptions {
                 enabled: raw.git,
@@ -86,16 +376,145 @@ impl AppConfig {
             runtime: RuntimeOptions {
                 measure_time: raw.time,
                 verbose: raw.verbose,
-                root: raw.root.into(),
+                roots,
+                verify: raw.verify.map(PathBuf::from),
+                watch: raw.watch,
+                cache: raw.cache,
+                diff,
+                focus,
             },
         })
     }
+
+    /// The walk options of the `i`-th root, focused on the `--focus` targets
+    /// found in it.
+    pub fn walk_for(&self, i: usize) -> Cow<'_, WalkOptions> {
+        match self.runtime.focus.get(i) {
+            Some(focus) if !focus.is_empty() => Cow::Owned(WalkOptions {
+                focus: focus.clone(),
+                ..self.walk.clone()
+            }),
+            _ => Cow::Borrowed(&self.walk),
+        }
+    }
+}
+
+/// Resolves `--focus` values against each root, keeping in each the targets
+/// it contains. A value found in no root is an error.
+fn focus_paths(roots: &[PathBuf], values: &[String]) -> Result<Vec<Vec<PathBuf>>, String> {
+    let mut found = vec![Vec::new(); roots.len()];
+    for value in values {
+        let mut result = Err(format!("--focus: {value}: no root given"));
+        for (targets, root) in found.iter_mut().zip(roots) {
+            match focus_path(root, value) {
+                Ok(rel) => {
+                    targets.push(rel);
+                    result = Ok(());
+                }
+                Err(e) if result.is_err() => result = Err(e),
+                Err(_) => {}
+            }
+        }
+        result?;
+    }
+    Ok(found)
+}
+
+/// Resolves a `--focus` value to a path relative to the root. Both
+/// `src/main.rs` and `<root>/src/main.rs` are accepted.
+fn focus_path(root: &Path, value: &str) -> Result<PathBuf, String> {
+    let path = Path::new(value);
+    let rel: PathBuf = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect();
+    if rel.is_absolute() || rel.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(format!("--focus: {value} is not inside {}", root.display()));
+    }
+    if std::fs::symlink_metadata(root.join(&rel)).is_err() {
+        return Err(format!("--focus: {value}: no such file or directory"));
+    }
+    Ok(rel)
+}
+
+/// Resolves a `--newer-than`/`--older-than` value to an instant. Accepts a
+/// duration back from now (`2h`), a local date or date-time
+/// (`2025-11-20`, `2025-11-20 14:30`) or, like `find -newer`, a file whose
+/// modification time is used.
+fn parse_time_spec(flag: &str, value: &str, now: SystemTime) -> Result<SystemTime, String> {
+    if let Ok(d) = helpers::parse_duration(value) {
+        return now
+            .checked_sub(d)
+            .ok_or_else(|| format!("{flag}: duration '{value}' is too large"));
+    }
+    if let Some(t) = parse_local_datetime(value) {
+        return Ok(t);
+    }
+    match std::fs::metadata(value).and_then(|m| m.modified()) {
+        Ok(t) => Ok(t),
+        Err(_) => Err(format!(
+            "{flag}: invalid value '{value}': expected a duration (2h, 3d), a date (2025-11-20) or an existing file"
+        )),
+    }
+}
+
+fn parse_local_datetime(value: &str) -> Option<SystemTime> {
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((d, t)) => (d, Some(t)),
+        None => (value, None),
+    };
+
+    let mut ymd = date.splitn(3, '-').map(|p| p.parse::<u32>().ok());
+    let (y, m, d) = (ymd.next()??, ymd.next()??, ymd.next()??);
+    let date = NaiveDate::from_ymd_opt(i32::try_from(y).ok()?, m, d)?;
+
+    let (h, min, sec) = match time {
+        Some(t) => {
+            let mut hms = t.splitn(3, ':').map(|p| p.parse::<u32>().ok());
+            (
+                hms.next()??,
+                hms.next()??,
+                hms.next().flatten().unwrap_or(0),
+            )
+        }
+        None => (0, 0, 0),
+    };
+    let naive = date.and_hms_opt(h, min, sec)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// The first option given that only applies to walked directories: the
+/// filters, and what reads file contents.
+fn walk_only_option(raw: &Args) -> Option<&'static str> {
+    [
+        ("--min-depth", raw.min_depth.is_some()),
+        ("--grep", raw.grep.is_some()),
+        ("--type", !raw.types.is_empty()),
+        ("--type-not", !raw.type_not.is_empty()),
+        ("--newer-than", raw.newer_than.is_some()),
+        ("--older-than", raw.older_than.is_some()),
+        ("--min-size", raw.min_size.is_some()),
+        ("--max-size", raw.max_size.is_some()),
+        ("--focus", !raw.focus.is_empty()),
+        ("--hash", raw.hash.is_some()),
+        ("--duplicates", raw.duplicates),
+        ("--loc", raw.loc),
+        ("--manifest", raw.manifest),
+        ("--verify", raw.verify.is_some()),
+    ]
+    .into_iter()
+    .find_map(|(flag, given)| given.then_some(flag))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::args::{Args, ColorMode};
+    use crate::cli::args::Args;
     use clap::Parser;
 
     #[test]
@@ -112,6 +531,208 @@ mod tests {
         assert!(err.contains("--depth must be >= 1"));
     }
 
+    #[test]
+    fn min_depth_greater_than_depth_is_rejected() {
+        let args = Args::try_parse_from(["arbor", "--depth", "2", "--min-depth", "3"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--min-depth"));
+    }
+
+    #[test]
+    fn depth_hint_requires_depth() {
+        let args = Args::try_parse_from(["arbor", "--depth-hint"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--depth-hint requires --depth"));
+    }
+
+    #[test]
+    fn max_entries_zero_is_rejected() {
+        let args = Args::try_parse_from(["arbor", "--max-entries-per-dir", "0"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--max-entries-per-dir must be >= 1"));
+    }
+
+    #[test]
+    fn fromfile_rejects_walk_only_options() {
+        for flag in [&["--depth", "1"][..], &["--stat"]] {
+            let args = Args::try_parse_from([&["arbor", "--fromfile"][..], flag].concat()).unwrap();
+            assert!(AppConfig::from_raw(args).is_ok());
+        }
+        for flag in [
+            &["--grep", "x"][..],
+            &["--min-depth", "1"],
+            &["--duplicates"],
+            &["--manifest"],
+        ] {
+            let args =
+                Args::try_parse_from([&["arbor", "--fromfile=-"][..], flag].concat()).unwrap();
+            let err = AppConfig::from_raw(args).unwrap_err();
+            assert!(
+                err.contains(&format!("{} does not apply", flag[0])),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn tracked_rejects_walk_only_options() {
+        let args = Args::try_parse_from(["arbor", "--tracked", "-d", "1"]).unwrap();
+        assert!(AppConfig::from_raw(args).is_ok());
+        let args = Args::try_parse_from(["arbor", "--tracked", "--type", "rust"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--type does not apply"), "{err}");
+    }
+
+    #[test]
+    fn archive_roots_are_not_hashed() {
+        let dir = tempfile::tempdir().unwrap();
+        let tar = dir.path().join("release.tar");
+        std::fs::write(&tar, b"").unwrap();
+        let tar = tar.to_str().unwrap();
+        for flag in ["--manifest", "--hash=sha256"] {
+            let args = Args::try_parse_from(["arbor", tar, flag]).unwrap();
+            let err = AppConfig::from_raw(args).unwrap_err();
+            assert!(err.contains("do not apply"), "{err}");
+        }
+        let args = Args::try_parse_from(["arbor", tar, "-d", "1"]).unwrap();
+        assert!(AppConfig::from_raw(args).is_ok());
+    }
+
+    #[test]
+    fn themes_are_only_loaded_for_trees_that_show_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = dir.path().join("broken.toml");
+        std::fs::write(&theme, "[colours]\n").unwrap();
+        let theme = theme.to_str().unwrap();
+        for flags in [&["--json"][..], &["--count"], &["--color", "never"]] {
+            let args =
+                Args::try_parse_from([&["arbor", "--theme", theme][..], flags].concat()).unwrap();
+            assert!(AppConfig::from_raw(args).is_ok(), "{flags:?}");
+        }
+        let args = Args::try_parse_from(["arbor", "--theme", theme, "--color", "always"]).unwrap();
+        assert!(AppConfig::from_raw(args).is_err());
+    }
+
+    #[test]
+    fn indent_out_of_range_is_rejected() {
+        for n in ["1", "9"] {
+            let args = Args::try_parse_from(["arbor", "--indent", n]).unwrap();
+            let err = AppConfig::from_raw(args).unwrap_err();
+            assert!(err.contains("--indent must be between 2 and 8"));
+        }
+    }
+
+    #[test]
+    fn time_specs_accept_durations_dates_and_files() {
+        let now = SystemTime::now();
+        let t = parse_time_spec("--newer-than", "1h", now).unwrap();
+        assert_eq!(now.duration_since(t).unwrap().as_secs(), 3_600);
+
+        let day = parse_time_spec("--newer-than", "2025-11-20", now).unwrap();
+        let later = parse_time_spec("--newer-than", "2025-11-20 14:30", now).unwrap();
+        assert_eq!(
+            later.duration_since(day).unwrap().as_secs(),
+            14 * 3_600 + 30 * 60
+        );
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+        let mtime = file.as_file().metadata().unwrap().modified().unwrap();
+        assert_eq!(parse_time_spec("--older-than", path, now).unwrap(), mtime);
+
+        let err = parse_time_spec("--older-than", "yesterday-ish", now).unwrap_err();
+        assert!(err.contains("--older-than"));
+    }
+
+    #[test]
+    fn size_filters_are_parsed_and_checked() {
+        let args = Args::try_parse_from(["arbor", "--min-size", "50M"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert_eq!(cfg.walk.min_size, Some(50_000_000));
+        assert!(cfg.walk.has_file_filters());
+
+        let args = Args::try_parse_from(["arbor", "--min-size", "2k", "--max-size", "1k"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--min-size must not be greater than --max-size"));
+
+        let args = Args::try_parse_from(["arbor", "--max-size", "lots"]).unwrap();
+        let err = AppConfig::from_raw(args).unwrap_err();
+        assert!(err.contains("--max-size"));
+    }
+
+    #[test]
+    fn type_filters_are_validated() {
+        let args = Args::try_parse_from(["arbor", "--type", "rust", "--type-not", "md"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert_eq!(cfg.walk.types.select, vec!["rust".to_string()]);
+        assert_eq!(cfg.walk.types.negate, vec!["md".to_string()]);
+        assert!(cfg.walk.has_file_filters());
+
+        let args = Args::try_parse_from(["arbor", "--type", "nope"]).unwrap();
+        assert!(AppConfig::from_raw(args).unwrap_err().contains("nope"));
+    }
+
+    #[test]
+    fn grep_pattern_is_compiled() {
+        let args = Args::try_parse_from(["arbor", "--grep", "TODO|FIXME", "--grep-lines"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        let grep = cfg.walk.grep.as_ref().expect("grep options");
+        assert!(grep.regex.is_match(b"// FIXME"));
+        assert!(grep.lines);
+        assert!(cfg.walk.has_file_filters());
+
+        let args = Args::try_parse_from(["arbor", "--grep", "(unclosed"]).unwrap();
+        assert!(AppConfig::from_raw(args).unwrap_err().contains("--grep"));
+    }
+
+    #[test]
+    fn manifest_and_verify_imply_sha256() {
+        let args = Args::try_parse_from(["arbor", "--manifest"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert!(matches!(cfg.output, OutputFormat::Manifest));
+        assert_eq!(cfg.walk.hash, Some(HashAlgo::Sha256));
+
+        let args =
+            Args::try_parse_from(["arbor", "--verify", "SHA256SUMS", "--hash", "blake3"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert!(matches!(cfg.output, OutputFormat::Tree));
+        assert_eq!(cfg.walk.hash, Some(HashAlgo::Blake3));
+        assert_eq!(cfg.runtime.verify, Some(PathBuf::from("SHA256SUMS")));
+
+        let args = Args::try_parse_from(["arbor"]).unwrap();
+        assert_eq!(AppConfig::from_raw(args).unwrap().walk.hash, None);
+    }
+
+    #[test]
+    fn diff_rejects_other_modes() {
+        let args = Args::try_parse_from(["arbor", "diff", "a", "b", "--changed-only"]).unwrap();
+        let diff = AppConfig::from_raw(args).unwrap().runtime.diff.unwrap();
+        assert_eq!(diff.new, PathBuf::from("b"));
+        assert!(diff.changed_only);
+
+        let args = Args::try_parse_from(["arbor", "--watch", "diff", "a", "b"]).unwrap();
+        assert!(AppConfig::from_raw(args).unwrap_err().contains("--watch"));
+    }
+
+    #[test]
+    fn watch_takes_a_single_root() {
+        let args = Args::try_parse_from(["arbor", "--watch", "src"]).unwrap();
+        assert!(AppConfig::from_raw(args).unwrap().runtime.watch);
+
+        let args = Args::try_parse_from(["arbor", "--watch", "src", "docs"]).unwrap();
+        assert!(AppConfig::from_raw(args).unwrap_err().contains("--watch"));
+
+        assert!(Args::try_parse_from(["arbor", "--watch", "--cache"]).is_err());
+        assert!(Args::try_parse_from(["arbor", "--watch", "--tracked"]).is_err());
+    }
+
+    #[test]
+    fn type_list_selects_its_own_output() {
+        let args = Args::try_parse_from(["arbor", "--type-list"]).unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert!(matches!(cfg.output, OutputFormat::TypeList));
+    }
+
     #[test]
     fn defaults_map_to_stdout_mode() {
         let args = Args::try_parse_from(["arbor"]).unwrap();
@@ -122,16 +743,24 @@ mod tests {
         assert!(cfg.walk.follow_gitignore);
         assert!(!cfg.walk.include_hidden);
         assert!(cfg.walk.depth.is_none());
+        assert!(cfg.walk.min_depth.is_none());
+        assert!(!cfg.walk.depth_hint);
+        assert!(!cfg.walk.one_file_system);
+        assert!(!cfg.walk.has_file_filters());
 
-        assert_eq!(cfg.render.color, ColorMode::Auto);
         assert!(!cfg.render.icons);
+        assert_eq!(cfg.render.quoting, QuotingStyle::Escape);
+
+        assert!(!cfg.transform.compact);
+        assert_eq!(cfg.transform.sort, SortKey::Name);
+        assert!(cfg.transform.max_entries.is_none());
 
         assert!(!cfg.git.enabled);
         assert!(!cfg.git.show_branch);
 
         assert!(!cfg.runtime.measure_time);
         assert!(!cfg.runtime.verbose);
-        assert_eq!(cfg.runtime.root, PathBuf::from("."));
+        assert_eq!(cfg.runtime.roots, [PathBuf::from(".")]);
     }
 
     #[test]
@@ -164,7 +793,7 @@ mod tests {
         assert!(cfg.walk.include_hidden);
         assert_eq!(cfg.walk.depth, Some(3));
 
-        assert_eq!(cfg.render.color, ColorMode::Never);
+        assert!(!cfg.render.color);
         assert!(cfg.render.icons);
 
         assert!(cfg.git.enabled);
@@ -174,7 +803,49 @@ mod tests {
 
         assert!(cfg.runtime.measure_time);
         assert!(cfg.runtime.verbose);
-        assert_eq!(cfg.runtime.root, PathBuf::from("root_dir"));
+        assert_eq!(cfg.runtime.roots, [PathBuf::from("root_dir")]);
+    }
+
+    #[test]
+    fn focus_paths_are_relative_to_the_root() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            focus_path(root, "./src/main.rs").unwrap(),
+            PathBuf::from("src/main.rs")
+        );
+        let inside = root.join("src/lib.rs");
+        assert_eq!(
+            focus_path(root, inside.to_str().unwrap()).unwrap(),
+            PathBuf::from("src/lib.rs")
+        );
+        assert!(focus_path(root, "src/missing.rs").is_err());
+        assert!(focus_path(root, "../elsewhere").is_err());
+
+        let roots = [root.join("tests"), root.join("src")];
+        let values = ["renderer/stdout.rs".to_string()];
+        assert_eq!(
+            focus_paths(&roots, &values).unwrap(),
+            [vec![], vec![PathBuf::from("renderer/stdout.rs")]]
+        );
+        assert!(focus_paths(&roots, &["nowhere.rs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn each_root_is_focused_on_its_own_targets() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let (src, tests) = (root.join("src"), root.join("tests"));
+        let main = src.join("main.rs");
+        let args = Args::try_parse_from([
+            "arbor".as_ref(),
+            src.as_os_str(),
+            tests.as_os_str(),
+            "--focus".as_ref(),
+            main.as_os_str(),
+        ])
+        .unwrap();
+        let cfg = AppConfig::from_raw(args).unwrap();
+        assert_eq!(cfg.walk_for(0).focus, [PathBuf::from("main.rs")]);
+        assert!(cfg.walk_for(1).focus.is_empty());
     }
 
     #[test]
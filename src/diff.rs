@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    config::{AppConfig, DiffOptions, OutputFormat, WalkOptions},
+    fs_scan::{snapshot, walk},
+    model::node::{Change, Node},
+    renderer::{json, stdout},
+    transform,
+};
+
+/// Number of files in each `arbor diff` state.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+}
+
+impl Tally {
+    pub fn is_clean(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.modified == 0
+    }
+
+    fn count(&mut self, change: Option<Change>) {
+        match change {
+            Some(Change::Added) => self.added += 1,
+            Some(Change::Removed) => self.removed += 1,
+            Some(Change::Modified) => self.modified += 1,
+            None => self.unchanged += 1,
+        }
+    }
+}
+
+pub fn write_tally<W: Write>(mut w: W, t: &Tally) -> io::Result<()> {
+    writeln!(
+        w,
+        "{} added, {} removed, {} modified, {} unchanged",
+        t.added, t.removed, t.modified, t.unchanged
+    )
+}
+
+/// Compares the two sides of `opts` and writes the merged tree in the output
+/// format of `config`, followed by the tally in tree form, where the root is
+/// named after both sides.
+pub fn run<W: Write>(out: &mut W, opts: &DiffOptions, config: &AppConfig) -> io::Result<Tally> {
+    let walk = config.walk_for(0);
+    let old = read_side(&opts.old, &walk)?;
+    let new = read_side(&opts.new, &walk)?;
+    let mut tally = Tally::default();
+    let mut tree = diff(old, new, &mut tally);
+    if opts.changed_only {
+        hide_unchanged(&mut tree);
+    }
+    transform::apply(&mut tree, &config.transform);
+
+    match config.output {
+        OutputFormat::Json => json::render(&mut *out, &tree)?,
+        OutputFormat::Count => write_tally(&mut *out, &tally)?,
+        _ => {
+            // The JSON root keeps the name of the new side, so that it can be
+            // read back as a snapshot; the tree header names both.
+            tree.name = format!("{} → {}", opts.old.display(), opts.new.display());
+            tree.raw_name = None;
+            stdout::render(&mut *out, &tree, &config.render)?;
+            writeln!(out)?;
+            write_tally(&mut *out, &tally)?;
+        }
+    }
+    Ok(tally)
+}
+
+/// Walks a directory, or reads a snapshot written by `arbor --json`.
+fn read_side(path: &Path, opts: &WalkOptions) -> io::Result<Node> {
+    if path.is_dir() {
+        walk::walk_path(path, opts)
+    } else {
+        snapshot::read_tree(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+}
+
+/// Merges two trees into one holding the entries of both, marked as added
+/// (only in `new`), removed (only in `old`) or modified. Files are modified
+/// when their digests differ, or without digests on both sides, their size
+/// or mtime. Directories are modified when anything below them changed, and
+/// an entry that changed kind shows up twice: once removed and once added.
+pub fn diff(old: Node, new: Node, tally: &mut Tally) -> Node {
+    match (old.is_dir(), new.is_dir()) {
+        (true, true) => merge_dirs(old, new, tally),
+        (false, false) => {
+            let change = differs(&old, &new).then_some(Change::Modified);
+            tally.count(change);
+            let mut node = new;
+            node.meta.change = change;
+            node
+        }
+        _ => {
+            let name = new.name.clone();
+            let mut node = Node::new_dir(&name, replaced(old, new, tally).into());
+            node.meta.change = Some(Change::Modified);
+            node
+        }
+    }
+}
+
+/// The two sides of an entry that changed kind, marked removed and added.
+fn replaced(mut old: Node, mut new: Node, tally: &mut Tally) -> [Node; 2] {
+    mark_all(&mut old, Change::Removed, tally);
+    mark_all(&mut new, Change::Added, tally);
+    [old, new]
+}
+
+fn merge_dirs(old: Node, mut new: Node, tally: &mut Tally) -> Node {
+    let mut both: BTreeMap<Vec<u8>, (Option<Node>, Option<Node>)> = BTreeMap::new();
+    for c in old.children.into_iter().flatten() {
+        let key = c.name_bytes().to_vec();
+        both.entry(key).or_default().0 = Some(c);
+    }
+    for c in new.children.take().into_iter().flatten() {
+        let key = c.name_bytes().to_vec();
+        both.entry(key).or_default().1 = Some(c);
+    }
+
+    let mut kids: Vec<Node> = Vec::new();
+    for pair in both.into_values() {
+        match pair {
+            (Some(o), Some(n)) if o.is_dir() != n.is_dir() => {
+                kids.extend(replaced(o, n, tally));
+            }
+            (Some(o), Some(n)) => kids.push(diff(o, n, tally)),
+            (Some(mut o), None) => {
+                mark_all(&mut o, Change::Removed, tally);
+                kids.push(o);
+            }
+            (None, Some(mut n)) => {
+                mark_all(&mut n, Change::Added, tally);
+                kids.push(n);
+            }
+            (None, None) => {}
+        }
+    }
+    let changed = kids.iter().any(|k| k.meta.change.is_some());
+    new.meta.change = changed.then_some(Change::Modified);
+    new.children = Some(kids);
+    new
+}
+
+fn differs(old: &Node, new: &Node) -> bool {
+    if let (Some(a), Some(b)) = (&old.meta.digest, &new.meta.digest)
+        && a.algo == b.algo
+    {
+        return a.hex != b.hex;
+    }
+    known_and_different(old.meta.size, new.meta.size)
+        || known_and_different(old.meta.mtime, new.meta.mtime)
+}
+
+fn known_and_different<T: PartialEq>(a: Option<T>, b: Option<T>) -> bool {
+    matches!((a, b), (Some(a), Some(b)) if a != b)
+}
+
+fn mark_all(node: &mut Node, change: Change, tally: &mut Tally) {
+    node.meta.change = Some(change);
+    if !node.is_dir() {
+        tally.count(Some(change));
+    }
+    for c in node.children.iter_mut().flatten() {
+        mark_all(c, change, tally);
+    }
+}
+
+/// Drops the unchanged entries, and with them the directories where nothing
+/// changed.
+pub fn hide_unchanged(node: &mut Node) {
+    if let Some(children) = &mut node.children {
+        children.retain(|c| c.meta.change.is_some());
+        for c in children {
+            hide_unchanged(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::Digest;
+
+    fn file(name: &str, size: u64, mtime: i64) -> Node {
+        let mut n = Node::new_file(name, size);
+        n.meta.mtime = Some(mtime);
+        n
+    }
+
+    fn changes(node: &Node) -> Vec<(String, Option<Change>)> {
+        node.children_slice()
+            .iter()
+            .map(|c| (c.name.clone(), c.meta.change))
+            .collect()
+    }
+
+    #[test]
+    fn marks_added_removed_and_modified_entries() {
+        let old = Node::new_dir(
+            "a",
+            vec![
+                Node::new_dir("gone", vec![file("x", 1, 10)]),
+                file("same", 3, 10),
+                file("size", 3, 10),
+                file("touched", 3, 10),
+            ],
+        );
+        let new = Node::new_dir(
+            "b",
+            vec![
+                file("new", 1, 10),
+                file("same", 3, 10),
+                file("size", 4, 10),
+                file("touched", 3, 11),
+            ],
+        );
+        let mut tally = Tally::default();
+        let tree = diff(old, new, &mut tally);
+
+        assert_eq!(
+            changes(&tree),
+            [
+                ("gone".to_string(), Some(Change::Removed)),
+                ("new".to_string(), Some(Change::Added)),
+                ("same".to_string(), None),
+                ("size".to_string(), Some(Change::Modified)),
+                ("touched".to_string(), Some(Change::Modified)),
+            ]
+        );
+        assert_eq!(tree.meta.change, Some(Change::Modified));
+        assert_eq!(
+            tally,
+            Tally {
+                added: 1,
+                removed: 1,
+                modified: 2,
+                unchanged: 1
+            }
+        );
+    }
+
+    #[test]
+    fn digests_take_precedence_over_mtimes() {
+        let hashed = |mtime, hex: &str| {
+            let mut n = file("f", 3, mtime);
+            n.meta.digest = Some(Digest {
+                algo: "sha256",
+                hex: hex.to_string(),
+            });
+            n
+        };
+        let mut tally = Tally::default();
+        assert_eq!(
+            diff(hashed(1, "aa"), hashed(2, "aa"), &mut tally)
+                .meta
+                .change,
+            None
+        );
+        assert_eq!(
+            diff(hashed(1, "aa"), hashed(1, "bb"), &mut tally)
+                .meta
+                .change,
+            Some(Change::Modified)
+        );
+    }
+
+    #[test]
+    fn entries_that_changed_kind_are_removed_and_added() {
+        let old = Node::new_dir(
+            "a",
+            vec![Node::new_dir("x", vec![file("1", 1, 10), file("2", 1, 10)])],
+        );
+        let new = Node::new_dir("b", vec![file("x", 1, 10)]);
+        let mut tally = Tally::default();
+        let tree = diff(old, new, &mut tally);
+
+        assert_eq!(
+            changes(&tree),
+            [
+                ("x".to_string(), Some(Change::Removed)),
+                ("x".to_string(), Some(Change::Added)),
+            ]
+        );
+        assert_eq!(
+            changes(&tree.children_slice()[0]),
+            [
+                ("1".to_string(), Some(Change::Removed)),
+                ("2".to_string(), Some(Change::Removed)),
+            ]
+        );
+        assert_eq!(
+            tally,
+            Tally {
+                added: 1,
+                removed: 2,
+                modified: 0,
+                unchanged: 0
+            }
+        );
+    }
+
+    #[test]
+    fn hiding_unchanged_entries_drops_unchanged_directories() {
+        let old = Node::new_dir(
+            "a",
+            vec![
+                Node::new_dir("docs", vec![file("x", 1, 10)]),
+                Node::new_dir("src", vec![file("lib.rs", 1, 10), file("main.rs", 1, 10)]),
+            ],
+        );
+        let new = Node::new_dir(
+            "b",
+            vec![
+                Node::new_dir("docs", vec![file("x", 1, 10)]),
+                Node::new_dir("src", vec![file("lib.rs", 1, 10), file("main.rs", 2, 10)]),
+            ],
+        );
+        let mut tree = diff(old, new, &mut Tally::default());
+        hide_unchanged(&mut tree);
+        assert_eq!(
+            changes(&tree),
+            [("src".to_string(), Some(Change::Modified))]
+        );
+        assert_eq!(
+            changes(&tree.children_slice()[0]),
+            [("main.rs".to_string(), Some(Change::Modified))]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use flate2::read::GzDecoder;
+use std::{
+    ffi::{OsStr, OsString},
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use crate::{
+    fs_scan::fromfile::{self, PathInfo},
+    helpers,
+    model::node::Node,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// Archive format, from the file name. `.crate` files are gzipped tarballs.
+fn format_of(name: &OsStr) -> Option<Format> {
+    let name = name.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".crate") {
+        Some(Format::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(Format::Tar)
+    } else if name.ends_with(".zip") {
+        Some(Format::Zip)
+    } else {
+        None
+    }
+}
+
+pub fn is_archive(name: &OsStr) -> bool {
+    format_of(name).is_some()
+}
+
+/// Builds the tree of the entries of an archive, from its listing only:
+/// nothing is extracted. Tarballs are decompressed as a stream, zip files
+/// only have their central directory read.
+pub fn read_tree(path: &Path) -> io::Result<Node> {
+    let name = path.file_name().unwrap_or(OsStr::new("."));
+    let format = path
+        .file_name()
+        .and_then(format_of)
+        .ok_or_else(|| io::Error::other(format!("{}: not an archive", path.display())))?;
+    let file = BufReader::new(File::open(path)?);
+    let entries = match format {
+        Format::Tar => tar_entries(file)?,
+        Format::TarGz => tar_entries(GzDecoder::new(file))?,
+        Format::Zip => zip_entries(file)?,
+    };
+    let children = fromfile::build_children(entries.iter().map(|(p, i)| (p.as_os_str(), *i)));
+    Ok(Node::new_dir("", children).with_os_name(name))
+}
+
+/// Keeps `levels` levels of entries below `node`. With `hint`, directories
+/// cut off record their number of entries, like `--depth-hint` does.
+pub fn cut(node: &mut Node, levels: usize, hint: bool) {
+    let Some(children) = node.children.as_mut() else {
+        return;
+    };
+    if levels == 0 {
+        let hidden = children.len();
+        children.clear();
+        if hint && hidden > 0 {
+            node.meta.collapsed = Some(hidden);
+        }
+        return;
+    }
+    for c in children {
+        cut(c, levels - 1, hint);
+    }
+}
+
+fn tar_entries<R: Read>(reader: R) -> io::Result<Vec<(OsString, PathInfo)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        let kind = header.entry_type();
+        if kind.is_pax_global_extensions() || kind.is_pax_local_extensions() {
+            continue;
+        }
+        let mut path = helpers::os_string(&entry.path_bytes());
+        if kind.is_dir() && !path.as_encoded_bytes().ends_with(b"/") {
+            path.push("/");
+        }
+        let info = PathInfo {
+            size: (!kind.is_dir()).then(|| entry.size()),
+            mode: header.mode().ok(),
+        };
+        entries.push((path, info));
+    }
+    Ok(entries)
+}
+
+fn zip_entries<R: Read + io::Seek>(reader: R) -> io::Result<Vec<(OsString, PathInfo)>> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(io::Error::other)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(io::Error::other)?;
+        let path = helpers::os_string(file.name_raw());
+        let info = PathInfo {
+            size: (!file.is_dir()).then(|| file.size()),
+            mode: file.unix_mode(),
+        };
+        entries.push((path, info));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_archives_by_name() {
+        assert_eq!(format_of(OsStr::new("release.tar.gz")), Some(Format::TarGz));
+        assert_eq!(
+            format_of(OsStr::new("serde-1.0.0.crate")),
+            Some(Format::TarGz)
+        );
+        assert_eq!(format_of(OsStr::new("APP.ZIP")), Some(Format::Zip));
+        assert_eq!(format_of(OsStr::new("backup.tar")), Some(Format::Tar));
+        assert_eq!(format_of(OsStr::new("notes.txt")), None);
+        assert_eq!(format_of(OsStr::new("tar")), None);
+    }
+
+    #[test]
+    fn cut_keeps_levels_and_counts_the_rest() {
+        let mut root = Node::new_dir(
+            "a.zip",
+            vec![Node::new_dir(
+                "pkg",
+                vec![Node::new_file("x", 1), Node::new_file("y", 1)],
+            )],
+        );
+        cut(&mut root, 1, true);
+        let pkg = &root.children_slice()[0];
+        assert!(pkg.children_slice().is_empty());
+        assert_eq!(pkg.meta.collapsed, Some(2));
+
+        cut(&mut root, 0, false);
+        assert!(root.children_slice().is_empty());
+        assert_eq!(root.meta.collapsed, None);
+    }
+
+    #[test]
+    fn lists_tar_entries_with_sizes_and_modes() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "pkg/bin/run.sh", &b"hello"[..])
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "pkg/empty", &b""[..])
+            .unwrap();
+        let data = builder.into_inner().unwrap();
+
+        let entries = tar_entries(&data[..]).unwrap();
+        let children = fromfile::build_children(entries.iter().map(|(p, i)| (p.as_os_str(), *i)));
+        let pkg = &children[0];
+        assert_eq!(pkg.name, "pkg");
+        let bin = &pkg.children_slice()[0];
+        let run = &bin.children_slice()[0];
+        assert_eq!(run.name, "run.sh");
+        assert_eq!(run.meta.size, Some(5));
+        assert_eq!(run.meta.mode, Some(0o755));
+        let empty = &pkg.children_slice()[1];
+        assert!(empty.is_dir());
+        assert!(empty.children_slice().is_empty());
+    }
+}
@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::WalkOptions, model::node::LineCounts};
+
+/// Bumped whenever the layout of the cache files changes.
+const FORMAT: u32 = 1;
+/// Entries modified this close to the scan may still change within the same
+/// mtime tick, so they are not cached.
+const RACY: Duration = Duration::from_secs(2);
+/// Cache files are dropped, least recently written first, past this total.
+pub const MAX_BYTES: u64 = 64 * 1024 * 1024;
+/// Files ignore rules are read from, relative to the directory they apply to.
+const IGNORE_FILES: [&str; 3] = [".gitignore", ".ignore", ".git/info/exclude"];
+
+/// Size and modification time of an entry, in nanoseconds since the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    pub size: u64,
+    pub mtime: u64,
+}
+
+impl Stamp {
+    pub fn of(meta: &Metadata) -> Option<Self> {
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: meta.len(),
+            mtime: u64::try_from(mtime.as_nanos()).ok()?,
+        })
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        Self::of(&fs::symlink_metadata(path).ok()?)
+    }
+}
+
+/// File type of a listed entry. Other entries (symbolic links, sockets...)
+/// are only counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Kind {
+    Dir,
+    File,
+    Other,
+}
+
+/// Entry of a directory as the walker listed it, after ignore rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Listed {
+    pub name: String,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DirRecord {
+    stamp: Stamp,
+    /// Stamps of the ignore files of the directory, which can be edited in
+    /// place without touching the directory.
+    ignores: Vec<Option<Stamp>>,
+    entries: Vec<Listed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileRecord {
+    stamp: Stamp,
+    #[serde(default)]
+    digest: Option<String>,
+    #[serde(default)]
+    loc: Option<[u64; 3]>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    format: u32,
+    /// Stamps of the ignore files above the root and of the global one.
+    context: Vec<Option<Stamp>>,
+    dirs: HashMap<String, DirRecord>,
+    files: HashMap<String, FileRecord>,
+}
+
+/// Directory listings, digests and line counts of a previous walk of the
+/// same root with the same options, by path below the root.
+///
+/// A directory is listed again when its mtime or one of its ignore files
+/// changed, and a file is read again when its size or mtime changed. Only
+/// what the current walk uses is written back, so removed entries drop out.
+pub struct ScanCache {
+    file: PathBuf,
+    old: Store,
+    new: Store,
+    started: SystemTime,
+}
+
+impl ScanCache {
+    /// Opens the cache of `root` walked with `opts` in the user cache
+    /// directory. There is none for relative time filters, which change from
+    /// one run to the next.
+    pub fn open(root: &Path, opts: &WalkOptions) -> Option<Self> {
+        if opts.newer_than.is_some() || opts.older_than.is_some() {
+            return None;
+        }
+        Some(Self::load(&default_dir()?, root, opts))
+    }
+
+    /// Loads the cache of `root` walked with `opts` from `dir`. A missing,
+    /// unreadable or outdated cache file starts an empty cache.
+    pub fn load(dir: &Path, root: &Path, opts: &WalkOptions) -> Self {
+        let abs = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let key = blake3::Hasher::new()
+            .update(abs.as_os_str().as_encoded_bytes())
+            .update(b"\0")
+            .update(format!("{opts:?}").as_bytes())
+            .finalize();
+        let file = dir.join(format!("{}.json", &key.to_hex()[..32]));
+
+        let context = context_stamps(&abs);
+        let mut old: Store = fs::read(&file)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .filter(|s: &Store| s.format == FORMAT)
+            .unwrap_or_default();
+        if old.context != context {
+            old.dirs.clear();
+        }
+        Self {
+            file,
+            old,
+            new: Store {
+                format: FORMAT,
+                context,
+                ..Store::default()
+            },
+            started: SystemTime::now(),
+        }
+    }
+
+    /// Path of the cache file.
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    /// Cached entries of the directory `rel`, if neither the directory nor
+    /// its ignore files changed since.
+    pub fn listing(&self, rel: &str, stamp: Stamp, ignores: &[Option<Stamp>]) -> Option<&[Listed]> {
+        let rec = self.old.dirs.get(rel)?;
+        (rec.stamp == stamp && rec.ignores == ignores).then_some(rec.entries.as_slice())
+    }
+
+    /// Whether the ignore files of the directory `rel` are known not to have
+    /// changed since. Otherwise the listings below it cannot be trusted
+    /// either, as the rules they were listed with may be gone.
+    pub fn same_rules(&self, rel: &str, ignores: &[Option<Stamp>]) -> bool {
+        self.old
+            .dirs
+            .get(rel)
+            .is_some_and(|rec| rec.ignores == ignores)
+    }
+
+    /// Keeps the listing of the directory `rel` for the next walk.
+    pub fn keep_dir(
+        &mut self,
+        rel: &str,
+        stamp: Stamp,
+        ignores: Vec<Option<Stamp>>,
+        entries: Vec<Listed>,
+    ) {
+        if !self.settled(&stamp) || !ignores.iter().flatten().all(|s| self.settled(s)) {
+            return;
+        }
+        let rec = DirRecord {
+            stamp,
+            ignores,
+            entries,
+        };
+        self.new.dirs.insert(rel.to_string(), rec);
+    }
+
+    /// Cached digest of the file `rel`, if it did not change since.
+    pub fn digest(&self, rel: &str, stamp: Stamp) -> Option<&str> {
+        self.fresh_file(rel, stamp)?.digest.as_deref()
+    }
+
+    /// Cached line counts of the file `rel`, if it did not change since.
+    pub fn loc(&self, rel: &str, stamp: Stamp) -> Option<LineCounts> {
+        let [code, comments, blanks] = self.fresh_file(rel, stamp)?.loc?;
+        Some(LineCounts {
+            code,
+            comments,
+            blanks,
+        })
+    }
+
+    pub fn keep_digest(&mut self, rel: &str, stamp: Stamp, hex: &str) {
+        if let Some(rec) = self.kept_file(rel, stamp) {
+            rec.digest = Some(hex.to_string());
+        }
+    }
+
+    pub fn keep_loc(&mut self, rel: &str, stamp: Stamp, c: LineCounts) {
+        if let Some(rec) = self.kept_file(rel, stamp) {
+            rec.loc = Some([c.code, c.comments, c.blanks]);
+        }
+    }
+
+    fn fresh_file(&self, rel: &str, stamp: Stamp) -> Option<&FileRecord> {
+        self.old.files.get(rel).filter(|rec| rec.stamp == stamp)
+    }
+
+    fn kept_file(&mut self, rel: &str, stamp: Stamp) -> Option<&mut FileRecord> {
+        if !self.settled(&stamp) {
+            return None;
+        }
+        let rec = self
+            .new
+            .files
+            .entry(rel.to_string())
+            .or_insert_with(|| FileRecord {
+                stamp,
+                digest: None,
+                loc: None,
+            });
+        Some(rec)
+    }
+
+    /// Whether an entry was last modified long enough before the walk.
+    fn settled(&self, stamp: &Stamp) -> bool {
+        let mtime = UNIX_EPOCH + Duration::from_nanos(stamp.mtime);
+        mtime + RACY < self.started
+    }
+
+    /// Writes the cache back, then trims the cache directory to `MAX_BYTES`.
+    pub fn save(self) -> io::Result<()> {
+        let dir = self.file.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+        let data = serde_json::to_vec(&self.new).map_err(io::Error::other)?;
+        if data.len() as u64 > MAX_BYTES {
+            debug!("cache of {} bytes is too large to keep", data.len());
+            return match fs::remove_file(&self.file) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        // Written aside and renamed, so a concurrent run never reads half a
+        // file.
+        let tmp = self
+            .file
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &self.file)?;
+        trim(dir, MAX_BYTES)
+    }
+}
+
+/// Removes the least recently written cache files until the rest fits in
+/// `limit` bytes.
+pub fn trim(dir: &Path, limit: u64) -> io::Result<()> {
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(dir)?
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect();
+    files.sort_unstable_by_key(|f| std::cmp::Reverse(f.0));
+    let mut total = 0;
+    for (_, len, path) in files {
+        total += len;
+        if total > limit {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// `$XDG_CACHE_HOME/arbor`, or `~/.cache/arbor`.
+fn default_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+    Some(base.join("arbor"))
+}
+
+/// Stamps of the ignore files of `dir`, missing ones included.
+pub fn ignore_stamps(dir: &Path) -> Vec<Option<Stamp>> {
+    IGNORE_FILES
+        .iter()
+        .map(|name| Stamp::read(&dir.join(name)))
+        .collect()
+}
+
+/// Stamps of the ignore files of every directory above `abs`, and of the
+/// global Git ignore file, whose rules apply to the whole walk.
+fn context_stamps(abs: &Path) -> Vec<Option<Stamp>> {
+    let mut stamps: Vec<Option<Stamp>> = abs.ancestors().skip(1).flat_map(ignore_stamps).collect();
+    let global = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .map(|p| p.join("git/ignore"));
+    stamps.push(global.and_then(|p| Stamp::read(&p)));
+    stamps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn old_stamp(mtime_secs: u64) -> Stamp {
+        Stamp {
+            size: 1,
+            mtime: mtime_secs * 1_000_000_000,
+        }
+    }
+
+    #[test]
+    fn keeps_only_settled_entries_and_round_trips() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("ws");
+        fs::create_dir(&root).unwrap();
+        let opts = WalkOptions::default();
+
+        let mut cache = ScanCache::load(tmp.path(), &root, &opts);
+        let now = Stamp::of(&fs::metadata(&root).unwrap()).unwrap();
+        cache.keep_digest("old.rs", old_stamp(1_000), "aa");
+        cache.keep_digest("new.rs", now, "bb");
+        cache.save().unwrap();
+
+        let cache = ScanCache::load(tmp.path(), &root, &opts);
+        assert_eq!(cache.digest("old.rs", old_stamp(1_000)), Some("aa"));
+        assert_eq!(cache.digest("old.rs", old_stamp(1_001)), None);
+        assert_eq!(cache.digest("new.rs", now), None);
+
+        let other = WalkOptions {
+            loc: true,
+            ..Default::default()
+        };
+        let cache = ScanCache::load(tmp.path(), &root, &other);
+        assert_eq!(cache.digest("old.rs", old_stamp(1_000)), None);
+    }
+
+    #[test]
+    fn trims_the_oldest_files_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        for (i, name) in ["a.json", "b.json", "c.json"].iter().enumerate() {
+            let p = tmp.path().join(name);
+            fs::write(&p, [0u8; 10]).unwrap();
+            let f = fs::File::options().write(true).open(&p).unwrap();
+            f.set_modified(UNIX_EPOCH + Duration::from_secs(1_000 + i as u64))
+                .unwrap();
+        }
+        trim(tmp.path(), 25).unwrap();
+        assert!(!tmp.path().join("a.json").exists());
+        assert!(tmp.path().join("b.json").exists());
+        assert!(tmp.path().join("c.json").exists());
+    }
+}
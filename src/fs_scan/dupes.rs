@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::args::HashAlgo,
+    fs_scan::{hash, pool},
+    helpers,
+    model::node::Node,
+};
+
+/// Number of leading bytes compared before hashing whole files.
+const HEAD_LEN: usize = 4096;
+
+/// Groups files with the same contents. Files are first grouped by size,
+/// then by a hash of their first bytes, and only then hashed in full, so
+/// that most files are never read past their first block. Empty and
+/// unreadable files are left out.
+///
+/// Returns groups of at least two indices into `files`, in the order of
+/// their first file.
+pub fn find_groups(files: &[(PathBuf, u64)]) -> Vec<Vec<usize>> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, (_, size)) in files.iter().enumerate() {
+        if *size > 0 {
+            by_size.entry(*size).or_default().push(i);
+        }
+    }
+    let candidates: Vec<usize> = by_size
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .flatten()
+        .collect();
+
+    let paths: Vec<PathBuf> = candidates.iter().map(|&i| files[i].0.clone()).collect();
+    let heads = pool::map_paths(&paths, |p| hash_head(p).ok());
+    let mut by_head: HashMap<(u64, blake3::Hash), Vec<usize>> = HashMap::new();
+    for (&i, head) in candidates.iter().zip(heads) {
+        if let Some(h) = head {
+            by_head.entry((files[i].1, h)).or_default().push(i);
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut to_hash = Vec::new();
+    for ((size, _), g) in by_head {
+        match g.len() {
+            0 | 1 => {}
+            // The first block was the whole file.
+            _ if size <= HEAD_LEN as u64 => groups.push(g),
+            _ => to_hash.extend(g),
+        }
+    }
+
+    let paths: Vec<PathBuf> = to_hash.iter().map(|&i| files[i].0.clone()).collect();
+    let digests = hash::hash_all(&paths, HashAlgo::Blake3);
+    let mut by_digest: HashMap<String, Vec<usize>> = HashMap::new();
+    for (&i, d) in to_hash.iter().zip(digests) {
+        if let Some(d) = d {
+            by_digest.entry(d.hex).or_default().push(i);
+        }
+    }
+    groups.extend(by_digest.into_values().filter(|g| g.len() > 1));
+
+    for g in &mut groups {
+        g.sort_unstable();
+    }
+    groups.sort_unstable_by_key(|g| g[0]);
+    groups
+}
+
+fn hash_head(path: &Path) -> io::Result<blake3::Hash> {
+    let mut buf = Vec::with_capacity(HEAD_LEN);
+    File::open(path)?
+        .take(HEAD_LEN as u64)
+        .read_to_end(&mut buf)?;
+    Ok(blake3::hash(&buf))
+}
+
+/// Duplicate groups, redundant copies and reclaimable bytes of one or more
+/// trees.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub groups: usize,
+    /// Copies beyond the first of each group.
+    pub redundant: usize,
+    pub reclaimable: u64,
+}
+
+impl Summary {
+    pub fn add(&mut self, root: &Node) {
+        let mut seen = HashSet::new();
+        self.walk(root, &mut seen);
+    }
+
+    fn walk(&mut self, node: &Node, seen: &mut HashSet<usize>) {
+        if let Some(d) = &node.meta.duplicates
+            && seen.insert(d.group)
+        {
+            self.groups += 1;
+            self.redundant += d.others.len();
+            self.reclaimable += d.reclaimable;
+        }
+        for c in node.children_slice() {
+            self.walk(c, seen);
+        }
+    }
+}
+
+pub fn write_summary<W: Write>(mut w: W, s: &Summary) -> io::Result<()> {
+    let groups = match s.groups {
+        1 => String::from("1 duplicate group"),
+        n => format!("{} duplicate groups", helpers::format_count(n)),
+    };
+    let redundant = match s.redundant {
+        1 => String::from("1 redundant file"),
+        n => format!("{} redundant files", helpers::format_count(n)),
+    };
+    writeln!(
+        w,
+        "{groups}, {redundant}, {} reclaimable",
+        helpers::format_size(s.reclaimable)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn groups_files_with_the_same_contents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let big = vec![7u8; HEAD_LEN * 2];
+        let mut big_other = big.clone();
+        *big_other.last_mut().unwrap() = 8;
+        let files: Vec<(PathBuf, u64)> = [
+            ("a.txt", &b"same"[..]),
+            ("b.txt", b"diff"),
+            ("c.txt", b"same"),
+            ("empty1", b""),
+            ("empty2", b""),
+            ("big1", &big),
+            ("big2", &big_other),
+            ("big3", &big),
+        ]
+        .iter()
+        .map(|(name, data)| {
+            let p = tmp.path().join(name);
+            fs::write(&p, data).unwrap();
+            (p, data.len() as u64)
+        })
+        .collect();
+
+        assert_eq!(find_groups(&files), vec![vec![0, 2], vec![5, 7]]);
+    }
+
+    #[test]
+    fn summary_counts_agree_with_their_nouns() {
+        let line = |groups, redundant| {
+            let mut buf = Vec::new();
+            let s = Summary {
+                groups,
+                redundant,
+                reclaimable: 2048,
+            };
+            write_summary(&mut buf, &s).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!(
+            line(1, 1),
+            "1 duplicate group, 1 redundant file, 2.0 KiB reclaimable\n"
+        );
+        assert_eq!(
+            line(2, 3),
+            "2 duplicate groups, 3 redundant files, 2.0 KiB reclaimable\n"
+        );
+    }
+}
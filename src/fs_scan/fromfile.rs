@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Component, Path},
+};
+
+use crate::{helpers, model::node::Node};
+
+/// What a path list, the Git index or an archive tells about a file.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct PathInfo {
+    pub size: Option<u64>,
+    pub mode: Option<u32>,
+}
+
+#[derive(Default, Debug)]
+struct TmpNode {
+    children: BTreeMap<OsString, TmpNode>,
+    is_dir: bool,
+    info: PathInfo,
+}
+
+/// Builds the tree of the paths listed in `source` (`-` for stdin) instead
+/// of walking the filesystem. Paths are relative to `root`, which names the
+/// tree. With `stat`, kinds and sizes are read from the filesystem; otherwise
+/// an entry is a directory only when it has children or a trailing slash, and
+/// files have no size.
+pub fn read_tree(source: &Path, root: &Path, stat: bool) -> io::Result<Node> {
+    let mut data = Vec::new();
+    if source == Path::new("-") {
+        io::stdin().lock().read_to_end(&mut data)?;
+    } else {
+        File::open(source)?.read_to_end(&mut data)?;
+    }
+    let paths: Vec<OsString> = split_paths(&data)
+        .into_iter()
+        .map(helpers::os_string)
+        .collect();
+    Ok(build_tree(root, &paths, stat))
+}
+
+/// Splits a path list on NUL bytes when there are any (`find -print0`,
+/// `git ls-files -z`), on line breaks otherwise.
+pub fn split_paths(data: &[u8]) -> Vec<&[u8]> {
+    let nul = data.contains(&0);
+    data.split(|&b| if nul { b == 0 } else { b == b'\n' })
+        .map(|p| {
+            if nul {
+                p
+            } else {
+                p.strip_suffix(b"\r").unwrap_or(p)
+            }
+        })
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+pub fn build_tree(root: &Path, paths: &[OsString], stat: bool) -> Node {
+    let top = collect(paths.iter().map(|p| (p.as_os_str(), PathInfo::default())));
+    let root_name = root.file_name().unwrap_or(OsStr::new("."));
+    materialize(root_name, top, root, stat)
+}
+
+/// Builds the children of a tree from paths whose size and mode are already
+/// known, without touching the filesystem.
+pub fn build_children<'a>(entries: impl IntoIterator<Item = (&'a OsStr, PathInfo)>) -> Vec<Node> {
+    let top = collect(entries);
+    top.children
+        .into_iter()
+        .map(|(n, c)| materialize(&n, c, Path::new(""), false))
+        .collect()
+}
+
+fn collect<'a>(entries: impl IntoIterator<Item = (&'a OsStr, PathInfo)>) -> TmpNode {
+    let mut top = TmpNode {
+        is_dir: true,
+        ..Default::default()
+    };
+    for (p, info) in entries {
+        let mut cur = &mut top;
+        for c in Path::new(p).components() {
+            let name = match c {
+                Component::Normal(n) => n,
+                Component::ParentDir => OsStr::new(".."),
+                _ => continue,
+            };
+            cur.is_dir = true;
+            cur = cur.children.entry(name.to_os_string()).or_default();
+        }
+        if p.as_encoded_bytes().ends_with(b"/") {
+            cur.is_dir = true;
+        }
+        cur.info = info;
+    }
+    top
+}
+
+fn materialize(name: &OsStr, tmp: TmpNode, path: &Path, stat: bool) -> Node {
+    let meta = if stat {
+        fs::symlink_metadata(path).ok()
+    } else {
+        None
+    };
+
+    if tmp.is_dir || meta.as_ref().is_some_and(|m| m.is_dir()) {
+        let kids = tmp
+            .children
+            .into_iter()
+            .map(|(n, c)| {
+                let p = path.join(&n);
+                materialize(&n, c, &p, stat)
+            })
+            .collect();
+        let mut node = Node::new_dir("", kids).with_os_name(name);
+        node.meta.mode = tmp.info.mode;
+        node
+    } else {
+        let mut node = Node::new_file("", 0).with_os_name(name);
+        node.meta.size = meta.map(|m| m.len()).or(tmp.info.size);
+        node.meta.mode = tmp.info.mode;
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::Kind;
+
+    fn paths(list: &[&str]) -> Vec<OsString> {
+        list.iter().map(OsString::from).collect()
+    }
+
+    fn names(n: &Node) -> Vec<&str> {
+        n.children_slice().iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_on_newlines_or_nul() {
+        assert_eq!(
+            split_paths(b"src/a.rs\r\nsrc/b.rs\n\nREADME.md\n"),
+            [&b"src/a.rs"[..], b"src/b.rs", b"README.md"]
+        );
+        assert_eq!(
+            split_paths(b"with\nnewline\0plain\0"),
+            [&b"with\nnewline"[..], b"plain"]
+        );
+    }
+
+    #[test]
+    fn builds_sorted_tree_from_paths() {
+        let root = build_tree(
+            Path::new("."),
+            &paths(&["src/main.rs", "./README.md", "src/cli/args.rs", "empty/"]),
+            false,
+        );
+        assert_eq!(root.name, ".");
+        assert_eq!(names(&root), ["README.md", "empty", "src"]);
+        assert!(root.children_slice()[1].is_dir());
+
+        let src = &root.children_slice()[2];
+        assert_eq!(names(src), ["cli", "main.rs"]);
+        let main = &src.children_slice()[1];
+        assert_eq!(main.meta.kind, Kind::File);
+        assert_eq!(main.meta.size, None);
+    }
+
+    #[test]
+    fn stat_reads_kinds_and_sizes() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("dir")).unwrap();
+        fs::write(tmp.path().join("data.bin"), b"12345").unwrap();
+
+        let root = build_tree(tmp.path(), &paths(&["dir", "data.bin", "gone.txt"]), true);
+        assert_eq!(names(&root), ["data.bin", "dir", "gone.txt"]);
+        assert_eq!(root.children_slice()[0].meta.size, Some(5));
+        assert!(root.children_slice()[1].is_dir());
+        assert_eq!(root.children_slice()[2].meta.size, None);
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::{
+    fs_scan::pool,
+    model::node::{ContentMatches, MatchLine},
+};
+use regex::bytes::Regex;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Files with a NUL byte in their first 8 KiB are treated as binary.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Matched lines longer than this are cut when kept for display.
+const MAX_LINE_CHARS: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    pub regex: Regex,
+    /// Keep the matching lines, not only their count.
+    pub lines: bool,
+}
+
+/// Searches a file line by line. Returns `None` for binary files and files
+/// without any match.
+pub fn search_file(path: &Path, opts: &GrepOptions) -> io::Result<Option<ContentMatches>> {
+    Ok(read_text(path)?.and_then(|data| search_bytes(&data, opts)))
+}
+
+/// Reads a file, unless its first 8 KiB show it is binary, in which case
+/// the rest is never read.
+pub fn read_text(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let mut data = Vec::new();
+    (&mut file)
+        .take(BINARY_SNIFF_LEN as u64)
+        .read_to_end(&mut data)?;
+    if looks_binary(&data) {
+        return Ok(None);
+    }
+    file.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+pub fn search_bytes(data: &[u8], opts: &GrepOptions) -> Option<ContentMatches> {
+    if looks_binary(data) {
+        return None;
+    }
+
+    let mut found = ContentMatches::default();
+    for (i, line) in data.split(|&b| b == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if !opts.regex.is_match(line) {
+            continue;
+        }
+        found.count += 1;
+        if opts.lines {
+            found.lines.push(MatchLine {
+                number: i + 1,
+                text: display_line(line),
+            });
+        }
+    }
+
+    (found.count > 0).then_some(found)
+}
+
+pub fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+fn display_line(line: &[u8]) -> String {
+    let text = String::from_utf8_lossy(line);
+    let text = text.trim();
+    match text.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Searches every file in parallel. Results are in the same order as
+/// `paths`; unreadable files count as no match.
+pub fn search_all(paths: &[PathBuf], opts: &GrepOptions) -> Vec<Option<ContentMatches>> {
+    pool::map_paths(paths, |p| search_file(p, opts).ok().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(pattern: &str, lines: bool) -> GrepOptions {
+        GrepOptions {
+            regex: Regex::new(pattern).unwrap(),
+            lines,
+        }
+    }
+
+    #[test]
+    fn counts_matching_lines() {
+        let text = b"fn main() {\n    // TODO: a\n    // FIXME b\n}\n";
+        let m = search_bytes(text, &opts("TODO|FIXME", false)).unwrap();
+        assert_eq!(m.count, 2);
+        assert!(m.lines.is_empty());
+    }
+
+    #[test]
+    fn keeps_trimmed_lines_with_numbers() {
+        let text = b"a\r\n\tTODO one\r\nb\n";
+        let m = search_bytes(text, &opts("TODO", true)).unwrap();
+        assert_eq!(
+            m.lines,
+            vec![MatchLine {
+                number: 2,
+                text: "TODO one".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn skips_binary_and_non_matching_data() {
+        assert!(search_bytes(b"TODO\x00\x01", &opts("TODO", false)).is_none());
+        assert!(search_bytes(b"nothing here", &opts("TODO", false)).is_none());
+    }
+
+    #[test]
+    fn cuts_long_lines() {
+        let line = format!("TODO {}", "x".repeat(500));
+        let m = search_bytes(line.as_bytes(), &opts("TODO", true)).unwrap();
+        assert_eq!(m.lines[0].text.chars().count(), MAX_LINE_CHARS + 1);
+        assert!(m.lines[0].text.ends_with('…'));
+    }
+
+    #[test]
+    fn reads_text_files_whole_and_binary_ones_no_further() {
+        let tmp = tempfile::tempdir().unwrap();
+        let text = tmp.path().join("long.txt");
+        let long = "line\n".repeat(BINARY_SNIFF_LEN);
+        std::fs::write(&text, &long).unwrap();
+        assert_eq!(read_text(&text).unwrap(), Some(long.into_bytes()));
+
+        let bin = tmp.path().join("a.bin");
+        std::fs::write(&bin, b"\x00TODO").unwrap();
+        assert_eq!(read_text(&bin).unwrap(), None);
+    }
+}
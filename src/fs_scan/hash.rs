@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use sha2::{Digest as _, Sha256};
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{cli::args::HashAlgo, fs_scan::pool, model::node::Digest};
+
+/// Hashes a file as a stream, without reading it into memory at once.
+pub fn hash_file(path: &Path, algo: HashAlgo) -> io::Result<Digest> {
+    let mut file = File::open(path)?;
+    let hex = match algo {
+        HashAlgo::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            io::copy(&mut file, &mut hasher)?;
+            hasher.finalize().to_hex().to_string()
+        }
+        HashAlgo::Sha256 => {
+            let mut hasher = Sha256::new();
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                match file.read(&mut buf)? {
+                    0 => break,
+                    n => hasher.update(&buf[..n]),
+                }
+            }
+            to_hex(&hasher.finalize())
+        }
+    };
+    Ok(Digest {
+        algo: algo.name(),
+        hex,
+    })
+}
+
+/// Hashes every file in parallel. Results are in the same order as `paths`;
+/// unreadable files have no digest.
+pub fn hash_all(paths: &[PathBuf], algo: HashAlgo) -> Vec<Option<Digest>> {
+    pool::map_paths(paths, |p| hash_file(p, algo).ok())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{b:02x}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_known_vectors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("abc.txt");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let sha = hash_file(&path, HashAlgo::Sha256).unwrap();
+        assert_eq!(sha.algo, "sha256");
+        assert_eq!(
+            sha.hex,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(sha.short(), "ba7816bf8f01");
+
+        let b3 = hash_file(&path, HashAlgo::Blake3).unwrap();
+        assert_eq!(
+            b3.hex,
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use phf::phf_map;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fs_scan::{grep, pool},
+    model::node::LineCounts,
+};
+
+/// Comment syntax of a language.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+const SLASHES: &[&str] = &["//"];
+const HASH: &[&str] = &["#"];
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+
+macro_rules! lang {
+    ($name:expr, $line:expr, $block:expr) => {
+        &Language {
+            name: $name,
+            line: $line,
+            block: $block,
+        }
+    };
+}
+
+/// Languages by extension. Only extensions that the icon table knows are
+/// listed, so a file with a language also has its icon.
+static LANGUAGES: phf::Map<&'static str, &'static Language> = phf_map! {
+    "rs" => lang!("Rust", SLASHES, C_BLOCK),
+    "c" => lang!("C", SLASHES, C_BLOCK),
+    "h" => lang!("C", SLASHES, C_BLOCK),
+    "cc" => lang!("C++", SLASHES, C_BLOCK),
+    "cpp" => lang!("C++", SLASHES, C_BLOCK),
+    "cxx" => lang!("C++", SLASHES, C_BLOCK),
+    "hh" => lang!("C++", SLASHES, C_BLOCK),
+    "hpp" => lang!("C++", SLASHES, C_BLOCK),
+    "hxx" => lang!("C++", SLASHES, C_BLOCK),
+    "cs" => lang!("C#", SLASHES, C_BLOCK),
+    "go" => lang!("Go", SLASHES, C_BLOCK),
+    "js" => lang!("JavaScript", SLASHES, C_BLOCK),
+    "cjs" => lang!("JavaScript", SLASHES, C_BLOCK),
+    "mjs" => lang!("JavaScript", SLASHES, C_BLOCK),
+    "jsx" => lang!("JSX", SLASHES, C_BLOCK),
+    "ts" => lang!("TypeScript", SLASHES, C_BLOCK),
+    "cts" => lang!("TypeScript", SLASHES, C_BLOCK),
+    "tsx" => lang!("TSX", SLASHES, C_BLOCK),
+    "css" => lang!("CSS", &[], C_BLOCK),
+    "sql" => lang!("SQL", &["--"], C_BLOCK),
+    "py" => lang!("Python", HASH, None),
+    "sh" => lang!("Shell", HASH, None),
+    "zsh" => lang!("Zsh", HASH, None),
+    "fish" => lang!("Fish", HASH, None),
+    "cmake" => lang!("CMake", HASH, None),
+    "toml" => lang!("TOML", HASH, None),
+    "yaml" => lang!("YAML", HASH, None),
+    "yml" => lang!("YAML", HASH, None),
+    "ini" => lang!("INI", &[";", "#"], None),
+    "json" => lang!("JSON", &[], None),
+    "md" => lang!("Markdown", &[], Some(("<!--", "-->"))),
+    "mdx" => lang!("Markdown", &[], Some(("<!--", "-->"))),
+};
+
+/// Language of a file, from its extension.
+pub fn language_of(name: &str) -> Option<&'static Language> {
+    let (_, ext) = name.rsplit_once('.')?;
+    LANGUAGES.get(ext).copied()
+}
+
+/// Counts the lines of a file. Returns `None` for binary files.
+pub fn count_file(path: &Path, lang: &Language) -> io::Result<Option<LineCounts>> {
+    Ok(grep::read_text(path)?.map(|data| count_lines(&data, lang)))
+}
+
+/// Counts the lines of every file in parallel. Results are in the same order
+/// as `paths`; files of unknown language, unreadable and binary files have no
+/// counts.
+pub fn count_all(paths: &[PathBuf]) -> Vec<Option<(&'static Language, LineCounts)>> {
+    pool::map_paths(paths, |p| {
+        let lang = language_of(p.file_name()?.to_str()?)?;
+        let counts = count_file(p, lang).ok().flatten()?;
+        Some((lang, counts))
+    })
+}
+
+/// Sorts lines into code, comments and blanks. Comment markers are only
+/// recognized at the start of a line, or as a block comment opened after
+/// code, without looking into strings.
+pub fn count_lines(data: &[u8], lang: &Language) -> LineCounts {
+    let mut c = LineCounts::default();
+    // Closing delimiter of the block comment the current line is in.
+    let mut in_block: Option<&str> = None;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        let t = line.trim_ascii();
+        if t.is_empty() {
+            c.blanks += 1;
+            continue;
+        }
+        if let Some(close) = in_block {
+            match find(t, close) {
+                Some(end) => {
+                    in_block = None;
+                    count_after_comment(&mut c, &t[end + close.len()..], lang);
+                }
+                None => c.comments += 1,
+            }
+            continue;
+        }
+        if lang.line.iter().any(|p| t.starts_with(p.as_bytes())) {
+            c.comments += 1;
+            continue;
+        }
+        if let Some((open, close)) = lang.block {
+            if let Some(after) = t.strip_prefix(open.as_bytes()) {
+                match find(after, close) {
+                    Some(end) => count_after_comment(&mut c, &after[end + close.len()..], lang),
+                    None => {
+                        in_block = Some(close);
+                        c.comments += 1;
+                    }
+                }
+                continue;
+            }
+            if let Some(start) = find(t, open)
+                && find(&t[start + open.len()..], close).is_none()
+            {
+                in_block = Some(close);
+            }
+        }
+        c.code += 1;
+    }
+    c
+}
+
+/// Counts a line whose comment ended: code if anything but a comment
+/// follows.
+fn count_after_comment(c: &mut LineCounts, rest: &[u8], lang: &Language) {
+    let rest = rest.trim_ascii();
+    if rest.is_empty() || lang.line.iter().any(|p| rest.starts_with(p.as_bytes())) {
+        c.comments += 1;
+    } else {
+        c.code += 1;
+    }
+}
+
+fn find(haystack: &[u8], needle: &str) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|w| w == needle.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::icons::EXTENSION_ICONS;
+
+    fn counts(code: u64, comments: u64, blanks: u64) -> LineCounts {
+        LineCounts {
+            code,
+            comments,
+            blanks,
+        }
+    }
+
+    #[test]
+    fn detects_languages_by_extension() {
+        assert_eq!(language_of("main.rs").unwrap().name, "Rust");
+        assert_eq!(language_of("setup.cfg.py").unwrap().name, "Python");
+        assert!(language_of("logo.png").is_none());
+        assert!(language_of("Makefile").is_none());
+    }
+
+    #[test]
+    fn every_language_extension_has_an_icon() {
+        for ext in LANGUAGES.keys() {
+            assert!(EXTENSION_ICONS.contains_key(ext), "{ext}");
+        }
+    }
+
+    #[test]
+    fn counts_code_comments_and_blanks() {
+        let rust = language_of("x.rs").unwrap();
+        let src = b"//! Crate docs\n\nfn main() { /* inline */\n    /* block\n\n       still */ let x = 1;\n    /* one line */\n}\n";
+        assert_eq!(count_lines(src, rust), counts(3, 3, 2));
+
+        let py = language_of("x.py").unwrap();
+        assert_eq!(count_lines(b"# c\r\nx = 1\r\n   \r\n", py), counts(1, 1, 1));
+    }
+
+    #[test]
+    fn code_opening_a_block_comment_continues_it() {
+        let c = language_of("x.c").unwrap();
+        let src = b"int x; /* starts\n   here */\nint y;\n";
+        assert_eq!(count_lines(src, c), counts(2, 1, 0));
+    }
+}
@@ -1,4 +1,16 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
+pub mod archive;
+pub mod cache;
+pub mod dupes;
+pub mod fromfile;
+pub mod grep;
+pub mod hash;
+pub mod loc;
+pub mod mounts;
+pub mod pool;
+pub mod snapshot;
+pub mod tracked;
+pub mod types;
 pub mod walk;
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use smol_str::SmolStr;
+use std::{collections::HashMap, path::PathBuf};
+
+/// Mount point → filesystem type.
+pub type MountMap = HashMap<PathBuf, SmolStr>;
+
+/// Reads the mount table of the current process. Returns an empty map on
+/// platforms without `/proc/self/mountinfo`.
+pub fn read_mounts() -> MountMap {
+    match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(text) => parse_mountinfo(&text),
+        Err(_) => MountMap::new(),
+    }
+}
+
+/// Parses the `proc(5)` mountinfo format:
+/// `36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 - ext3 /dev/root rw`
+/// The mount point is the 5th field, the filesystem type is the first field
+/// after the `-` separator.
+pub fn parse_mountinfo(text: &str) -> MountMap {
+    let mut map = MountMap::new();
+    for line in text.lines() {
+        let mut fields = line.split(' ');
+        let Some(mount_point) = fields.nth(4) else {
+            continue;
+        };
+        let Some(fs_type) = fields.skip_while(|f| *f != "-").nth(1) else {
+            continue;
+        };
+        map.insert(PathBuf::from(unescape(mount_point)), SmolStr::new(fs_type));
+    }
+    map
+}
+
+/// Mount points escape space, tab, newline and backslash as `\ooo`.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(code) = bytes
+                .get(i + 1..i + 4)
+                .and_then(|o| std::str::from_utf8(o).ok())
+                .and_then(|o| u8::from_str_radix(o, 8).ok())
+        {
+            out.push(code);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const SAMPLE: &str = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:22 / /proc rw,relatime - proc proc rw
+41 22 0:40 / /home/me/nas rw,relatime shared:20 master:3 - nfs4 srv:/export rw
+42 22 0:41 / /mnt/my\\040disk rw - vfat /dev/sdb1 rw
+";
+
+    #[test]
+    fn parses_mount_points_and_types() {
+        let map = parse_mountinfo(SAMPLE);
+        assert_eq!(map.get(Path::new("/")).map(|s| s.as_str()), Some("ext4"));
+        assert_eq!(
+            map.get(Path::new("/proc")).map(|s| s.as_str()),
+            Some("proc")
+        );
+        assert_eq!(
+            map.get(Path::new("/home/me/nas")).map(|s| s.as_str()),
+            Some("nfs4")
+        );
+    }
+
+    #[test]
+    fn unescapes_octal_sequences() {
+        let map = parse_mountinfo(SAMPLE);
+        assert_eq!(
+            map.get(Path::new("/mnt/my disk")).map(|s| s.as_str()),
+            Some("vfat")
+        );
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert!(parse_mountinfo("garbage\n1 2 3\n").is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Runs `f` on every path on a pool of scoped threads, one per available
+/// core. Results are in the same order as `paths`.
+pub fn map_paths<T, F>(paths: &[PathBuf], f: F) -> Vec<Option<T>>
+where
+    T: Send,
+    F: Fn(&Path) -> Option<T> + Sync,
+{
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(paths.len().max(1));
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<Option<T>> = paths.iter().map(|_| None).collect();
+    let found: Vec<Vec<(usize, T)>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut local = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(i) else {
+                            break;
+                        };
+                        if let Some(v) = f(path) {
+                            local.push((i, v));
+                        }
+                    }
+                    local
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+
+    for (i, v) in found.into_iter().flatten() {
+        results[i] = Some(v);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_order_of_paths() {
+        let paths: Vec<PathBuf> = (0..100).map(|i| PathBuf::from(i.to_string())).collect();
+        let out = map_paths(&paths, |p| {
+            let n: usize = p.to_str()?.parse().ok()?;
+            (!n.is_multiple_of(3)).then_some(n * 2)
+        });
+        assert_eq!(out.len(), 100);
+        assert_eq!(out[0], None);
+        assert_eq!(out[1], Some(2));
+        assert_eq!(out[98], Some(196));
+        assert_eq!(out[99], None);
+    }
+}
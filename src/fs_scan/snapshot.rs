@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use serde::Deserialize;
+use std::{fs, io, path::Path};
+
+use crate::{
+    helpers,
+    model::node::{Digest, Node},
+};
+
+/// An entry as written by `renderer::json`. Fields that only describe how
+/// the tree was shown (Git statuses, matches, counts) are not read back.
+#[derive(Deserialize)]
+struct JsonNode {
+    name: String,
+    #[serde(default)]
+    name_bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    segments: Option<Vec<String>>,
+    kind: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    mtime: Option<i64>,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    digest: Option<String>,
+    #[serde(default)]
+    children: Vec<JsonNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Snapshot {
+    One(JsonNode),
+    Many(Vec<JsonNode>),
+}
+
+/// Reads a tree saved with `arbor --json`. A snapshot of several roots must
+/// hold exactly one of them.
+pub fn read_tree(path: &Path) -> io::Result<Node> {
+    parse(&fs::read(path)?)
+}
+
+pub fn parse(data: &[u8]) -> io::Result<Node> {
+    let snapshot: Snapshot =
+        serde_json::from_slice(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let root = match snapshot {
+        Snapshot::One(root) => root,
+        Snapshot::Many(mut roots) if roots.len() == 1 => roots.remove(0),
+        Snapshot::Many(roots) => {
+            return Err(invalid(format!(
+                "the snapshot holds {} roots instead of one",
+                roots.len()
+            )));
+        }
+    };
+    to_node(root)
+}
+
+fn to_node(j: JsonNode) -> io::Result<Node> {
+    let mut node = match j.kind.as_str() {
+        "dir" => Node::new_dir(
+            "",
+            j.children
+                .into_iter()
+                .map(to_node)
+                .collect::<io::Result<_>>()?,
+        ),
+        "file" => Node::new_file("", 0),
+        other => return Err(invalid(format!("unknown kind \"{other}\""))),
+    };
+    node.meta.size = j.size;
+    node.meta.mtime = j.mtime;
+    node.meta.mode = j.mode.and_then(|m| u32::from_str_radix(&m, 8).ok());
+    node.meta.digest = j.digest.as_deref().and_then(parse_digest);
+
+    let Some(mut segments) = j.segments.filter(|s| s.len() > 1) else {
+        return Ok(match j.name_bytes {
+            Some(bytes) => node.with_os_name(&helpers::os_string(&bytes)),
+            None => {
+                node.name = j.name;
+                node
+            }
+        });
+    };
+    // A chain merged by `--compact`: nest the node back into its parents.
+    node.name = segments.pop().unwrap_or_default();
+    for name in segments.into_iter().rev() {
+        node = Node::new_dir(&name, vec![node]);
+    }
+    Ok(node)
+}
+
+fn parse_digest(s: &str) -> Option<Digest> {
+    let (algo, hex) = s.split_once(':')?;
+    let algo = match algo {
+        "sha256" => "sha256",
+        "blake3" => "blake3",
+        _ => return None,
+    };
+    Some(Digest {
+        algo,
+        hex: hex.to_string(),
+    })
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::json;
+
+    #[test]
+    fn reads_back_what_the_json_renderer_writes() {
+        let mut file = Node::new_file("main.rs", 12);
+        file.meta.mtime = Some(1_700_000_000);
+        file.meta.digest = Some(Digest {
+            algo: "blake3",
+            hex: "ab".to_string(),
+        });
+        let root = Node::new_dir(
+            ".",
+            vec![Node::new_dir("src", vec![file]), Node::new_file("a.txt", 1)],
+        );
+        let mut buf = Vec::new();
+        json::render(&mut buf, &root).unwrap();
+        assert_eq!(parse(&buf).unwrap(), root);
+
+        let mut buf = Vec::new();
+        json::render_many(&mut buf, &[root.clone(), root]).unwrap();
+        assert!(parse(&buf).is_err());
+    }
+
+    #[test]
+    fn expands_compacted_chains() {
+        let data = br#"{"name": "a/b", "segments": ["a", "b"], "kind": "dir", "size": null,
+            "children": [{"name": "x", "kind": "file", "size": 3}]}"#;
+        let a = parse(data).unwrap();
+        assert_eq!(a.name, "a");
+        let b = &a.children_slice()[0];
+        assert_eq!(b.name, "b");
+        assert_eq!(b.children_slice()[0].meta.size, Some(3));
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use git2::{Repository, Status, StatusOptions};
+use std::{
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fs_scan::fromfile::{self, PathInfo},
+    helpers,
+    model::node::Node,
+};
+
+/// Git mode of a submodule entry (a gitlink).
+const GITLINK_MODE: u32 = 0o160000;
+
+/// Builds the tree of the files in the Git index below `root`, without
+/// walking the filesystem. Sizes come from the index entries. With
+/// `untracked`, files that are neither tracked nor ignored are added, with
+/// their size read from the filesystem.
+pub fn read_tree(root: &Path, untracked: bool) -> io::Result<Node> {
+    let repo = Repository::discover(root).map_err(|_| {
+        io::Error::other(format!(
+            "--tracked: {} is not in a Git repository",
+            root.display()
+        ))
+    })?;
+    let prefix = repo_prefix(&repo, root)?;
+    let index = repo.index().map_err(io::Error::other)?;
+
+    let mut entries: Vec<(OsString, PathInfo)> = Vec::with_capacity(index.len());
+    for e in index.iter() {
+        let Some(rel) = strip_repo_prefix(&e.path, &prefix) else {
+            continue;
+        };
+        let mut path = helpers::os_string(rel);
+        if e.mode == GITLINK_MODE {
+            path.push("/");
+            entries.push((path, PathInfo::default()));
+        } else {
+            let info = PathInfo {
+                size: Some(u64::from(e.file_size)),
+                mode: Some(e.mode),
+            };
+            entries.push((path, info));
+        }
+    }
+
+    if untracked {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo.statuses(Some(&mut opts)).map_err(io::Error::other)?;
+        for s in statuses
+            .iter()
+            .filter(|s| s.status().contains(Status::WT_NEW))
+        {
+            let Some(rel) = strip_repo_prefix(s.path_bytes(), &prefix) else {
+                continue;
+            };
+            let path = helpers::os_string(rel);
+            let size = fs::symlink_metadata(root.join(&path)).ok().map(|m| m.len());
+            entries.push((path, PathInfo { size, mode: None }));
+        }
+    }
+
+    let root_name = root.file_name().unwrap_or(OsStr::new("."));
+    let children = fromfile::build_children(entries.iter().map(|(p, i)| (p.as_os_str(), *i)));
+    Ok(Node::new_dir("", children).with_os_name(root_name))
+}
+
+/// Path of `root` relative to the work tree, as index path bytes.
+fn repo_prefix(repo: &Repository, root: &Path) -> io::Result<Vec<u8>> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| io::Error::other("--tracked: bare repositories have no work tree"))?
+        .canonicalize()?;
+    let rel: PathBuf = root
+        .canonicalize()?
+        .strip_prefix(&workdir)
+        .map_err(io::Error::other)?
+        .to_path_buf();
+    Ok(rel.as_os_str().as_encoded_bytes().to_vec())
+}
+
+fn strip_repo_prefix<'a>(path: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+    path.strip_prefix(prefix)?.strip_prefix(b"/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_the_root_prefix() {
+        assert_eq!(
+            strip_repo_prefix(b"src/main.rs", b""),
+            Some(&b"src/main.rs"[..])
+        );
+        assert_eq!(
+            strip_repo_prefix(b"src/main.rs", b"src"),
+            Some(&b"main.rs"[..])
+        );
+        assert_eq!(strip_repo_prefix(b"srcs/main.rs", b"src"), None);
+        assert_eq!(strip_repo_prefix(b"docs/a.md", b"src"), None);
+    }
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use ignore::types::{Types, TypesBuilder};
+use std::io::{self, Write};
+
+/// File type selection, using the definitions shipped with the `ignore`
+/// crate (the same as ripgrep's `--type`).
+#[derive(Debug, Clone, Default)]
+pub struct TypeFilter {
+    /// Extra definitions, `name:glob` (e.g. `proto:*.proto`).
+    pub add: Vec<String>,
+    pub select: Vec<String>,
+    pub negate: Vec<String>,
+}
+
+impl TypeFilter {
+    pub fn is_active(&self) -> bool {
+        !self.select.is_empty() || !self.negate.is_empty()
+    }
+}
+
+fn builder(filter: &TypeFilter) -> Result<TypesBuilder, String> {
+    let mut tb = TypesBuilder::new();
+    tb.add_defaults();
+    for def in &filter.add {
+        tb.add_def(def)
+            .map_err(|e| format!("--type-add '{def}': {e}"))?;
+    }
+    Ok(tb)
+}
+
+/// Builds the matcher for the walker, or `None` when no type is selected.
+pub fn build_types(filter: &TypeFilter) -> Result<Option<Types>, String> {
+    if !filter.is_active() {
+        // still validate --type-add definitions
+        builder(filter)?;
+        return Ok(None);
+    }
+    let mut tb = builder(filter)?;
+    for name in &filter.select {
+        tb.select(name);
+    }
+    for name in &filter.negate {
+        tb.negate(name);
+    }
+    tb.build().map(Some).map_err(|e| e.to_string())
+}
+
+/// Prints every known type and its globs, one per line (`rust: *.rs`).
+pub fn write_type_list<W: Write>(mut w: W, filter: &TypeFilter) -> io::Result<()> {
+    let tb = builder(filter).map_err(io::Error::other)?;
+    for def in tb.definitions() {
+        writeln!(w, "{}: {}", def.name(), def.globs().join(", "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_selection_means_no_matcher() {
+        assert!(build_types(&TypeFilter::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_type_is_an_error() {
+        let filter = TypeFilter {
+            select: vec!["klingon".into()],
+            ..Default::default()
+        };
+        let err = build_types(&filter).unwrap_err();
+        assert!(err.contains("klingon"));
+    }
+
+    #[test]
+    fn invalid_definition_is_an_error() {
+        let filter = TypeFilter {
+            add: vec!["no-glob".into()],
+            ..Default::default()
+        };
+        assert!(build_types(&filter).unwrap_err().contains("--type-add"));
+    }
+
+    #[test]
+    fn custom_types_are_listed_and_selectable() {
+        let filter = TypeFilter {
+            add: vec!["proto:*.proto".into()],
+            select: vec!["proto".into()],
+            ..Default::default()
+        };
+        let types = build_types(&filter).unwrap().unwrap();
+        assert!(types.matched("api.proto", false).is_whitelist());
+        assert!(types.matched("main.rs", false).is_ignore());
+
+        let mut buf = Vec::new();
+        write_type_list(&mut buf, &filter).unwrap();
+        let list = String::from_utf8(buf).unwrap();
+        assert!(list.contains("proto: *.proto\n"));
+        assert!(list.contains("rust: *.rs\n"));
+    }
+}
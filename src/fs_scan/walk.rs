@@ -1,76 +1,114 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use ignore::{DirEntry, WalkBuilder};
+use ignore::{DirEntry, WalkBuilder, types::Types};
+use log::debug;
+use smol_str::SmolStr;
 use std::{
     collections::HashMap,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
+    fs::{self, Metadata},
     io,
     path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use crate::{config::WalkOptions, model::node::Node};
+use crate::{
+    config::WalkOptions,
+    fs_scan::{
+        archive,
+        cache::{Kind, Listed, ScanCache, Stamp, ignore_stamps},
+        dupes, grep, hash,
+        loc::{self, Language},
+        mounts::{self, MountMap},
+        types,
+    },
+    model::node::{ContentMatches, Digest, Duplicates, FileType, LineCounts, Node},
+};
 
 #[derive(Default, Debug)]
 struct TmpNode {
-    name: String,
+    name: OsString,
     size: u64,
     is_dir: bool,
     children: Vec<usize>,
+    collapsed: usize,
+    filtered: usize,
+    mount: Option<SmolStr>,
+    matches: Option<ContentMatches>,
+    /// Set on files dropped after the walk (no `--grep` match, no copy).
+    excluded: bool,
+    /// Set on `--focus` targets.
+    focused: bool,
+    /// Tree of an archive browsed with `--archives`.
+    archive: Option<Node>,
+    digest: Option<Digest>,
+    duplicates: Option<Duplicates>,
+    loc: Option<(&'static Language, LineCounts)>,
+    /// Size and mtime of files, when caching.
+    stamp: Option<Stamp>,
+    mtime: Option<i64>,
+    file_type: Option<FileType>,
 }
 
 pub fn walk_path(root: &Path, opts: &WalkOptions) -> io::Result<Node> {
-    let mut wb = WalkBuilder::new(root);
-    wb.follow_links(false)
-        .hidden(!opts.include_hidden)
-        .git_ignore(opts.follow_gitignore)
-        .git_exclude(opts.follow_gitignore)
-        .git_global(opts.follow_gitignore)
-        .threads(0)
-        .filter_entry({
-            let include_hidden = opts.include_hidden;
-            move |e: &DirEntry| {
-                if include_hidden {
-                    if is_dot_git_dir(e) {
-                        return true;
-                    }
-                    if has_dot_git_ancestor(e.path()) {
-                        return false;
-                    }
-                }
-                true
-            }
-        });
+    walk(root, opts, None)
+}
+
+/// Walks `root` like [`walk_path`], reusing the directory listings, digests
+/// and line counts of `cache` that are still current, and updating it.
+pub fn walk_path_cached(
+    root: &Path,
+    opts: &WalkOptions,
+    cache: &mut ScanCache,
+) -> io::Result<Node> {
+    walk(root, opts, Some(cache))
+}
+
+fn walk(root: &Path, opts: &WalkOptions, mut cache: Option<&mut ScanCache>) -> io::Result<Node> {
+    // With depth hints on, we walk one extra level so entries right below the
+    // limit can be counted on their parent without being materialized.
+    // `--focus` counts the entries it does not expand on its own.
+    let focus = Focus::new(root, opts);
+    let hint_depth = opts.depth.filter(|_| opts.depth_hint && focus.is_none());
+    let max_depth = hint_depth.map(|d| d + 1).or(opts.depth);
+
+    let lister = Lister {
+        opts,
+        types: types::build_types(&opts.types).map_err(io::Error::other)?,
+        focus: focus.clone(),
+        max_depth: if focus.is_some() { None } else { max_depth },
+    };
+    let entries: Vec<Entry> = match cache.as_deref_mut() {
+        Some(cache) => lister.replay(root, cache),
+        None => lister.list(root, 0, lister.max_depth).collect(),
+    };
 
     let mut nodes_by_path: HashMap<PathBuf, usize> = HashMap::new();
     let mut arena: Vec<TmpNode> = Vec::new();
 
-    let root_name = root
-        .file_name()
-        .map(|s| s.to_string_lossy().into_owned())
-        .unwrap_or_else(|| ".".into());
+    let root_name = root.file_name().unwrap_or(OsStr::new("."));
 
-    let root_idx = push_dir(&mut arena, &root_name);
+    let mount_table = MountTable::new(root);
+
+    let root_idx = push_dir(&mut arena, root_name);
+    arena[root_idx].mount = mount_table.lookup(root, root);
     nodes_by_path.insert(root.to_path_buf(), root_idx);
 
-    for result in wb.build() {
-        let entry = match result {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+    let mut grep_queue: Vec<(usize, PathBuf)> = Vec::new();
+    let mut hash_queue: Vec<(usize, PathBuf)> = Vec::new();
+    let mut dupes_queue: Vec<(usize, PathBuf)> = Vec::new();
+    let mut loc_queue: Vec<(usize, PathBuf)> = Vec::new();
 
-        if entry.depth() == 0 {
-            continue;
-        }
-        if entry.depth() > opts.depth.unwrap_or(100) {
+    for entry in entries {
+        // `WalkBuilder::min_depth` would also hide shallow directories from
+        // the ignore matcher, so shallow entries are filtered here instead and
+        // only re-created as ancestors of deeper ones.
+        if entry.depth == 0 || entry.depth < opts.min_depth.unwrap_or(0) {
             continue;
         }
 
-        if should_skip(&entry) {
-            continue;
-        }
-
-        let path = entry.path();
+        let path = entry.path.as_path();
         let parent_path = match path.parent() {
             Some(p) => p,
             None => continue,
@@ -79,53 +117,550 @@ pub fn walk_path(root: &Path, opts: &WalkOptions) -> io::Result<Node> {
         let parent_idx =
             ensure_dir_idx(parent_path, &mut nodes_by_path, &mut arena, root, root_idx);
 
-        match entry.file_type() {
-            Some(ft) if ft.is_dir() => {
-                let name = file_name_str(path);
-                let idx = push_dir(&mut arena, &name);
+        if let Some(d) = hint_depth
+            && entry.depth > d
+        {
+            arena[parent_idx].collapsed += 1;
+            continue;
+        }
+        if let Some(f) = &focus
+            && !f.expands(path, entry.depth)
+        {
+            arena[parent_idx].collapsed += 1;
+            continue;
+        }
+
+        match entry.kind {
+            Kind::Dir => {
+                let idx = push_dir(&mut arena, file_name_os(path));
+                arena[idx].mount = mount_table.lookup(root, path);
+                arena[idx].focused = focus.as_ref().is_some_and(|f| f.is_target(path));
                 nodes_by_path.insert(path.to_path_buf(), idx);
                 push_child(parent_idx, idx, &mut arena);
             }
-            Some(ft) if ft.is_file() => {
-                let size = entry.metadata().ok().map(|m| m.len()).unwrap_or(0);
-                let name = file_name_str(path);
-                let idx = push_file(&mut arena, &name, size);
+            Kind::File => {
+                let meta = entry.meta;
+                if !matches_file_filters(meta.as_ref(), opts) {
+                    arena[parent_idx].filtered += 1;
+                    continue;
+                }
+                let size = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+                let idx = push_file(&mut arena, file_name_os(path), size);
+                arena[idx].mtime = meta.as_ref().and_then(mtime_secs);
+                arena[idx].file_type = meta.as_ref().and_then(file_type);
+                if cache.is_some() {
+                    arena[idx].stamp = meta.as_ref().and_then(Stamp::of);
+                }
+                arena[idx].focused = focus.as_ref().is_some_and(|f| f.is_target(path));
                 push_child(parent_idx, idx, &mut arena);
+                if opts.grep.is_some() {
+                    grep_queue.push((idx, path.to_path_buf()));
+                }
+                if opts.hash.is_some() {
+                    hash_queue.push((idx, path.to_path_buf()));
+                }
+                if opts.duplicates {
+                    dupes_queue.push((idx, path.to_path_buf()));
+                }
+                if opts.loc
+                    && file_name_os(path)
+                        .to_str()
+                        .and_then(loc::language_of)
+                        .is_some()
+                {
+                    loc_queue.push((idx, path.to_path_buf()));
+                }
+                if opts.archives && archive::is_archive(file_name_os(path)) {
+                    arena[idx].archive = read_archive(path, entry.depth, opts);
+                }
+            }
+            Kind::Other => continue,
+        }
+    }
+
+    if let Some(g) = &opts.grep {
+        let paths: Vec<PathBuf> = grep_queue.iter().map(|(_, p)| p.clone()).collect();
+        let found = grep::search_all(&paths, g);
+        for ((idx, _), m) in grep_queue.into_iter().zip(found) {
+            arena[idx].excluded = m.is_none();
+            arena[idx].matches = m;
+        }
+    }
+
+    if opts.duplicates {
+        dupes_queue.retain(|(idx, _)| !arena[*idx].excluded);
+        mark_duplicates(&mut arena, dupes_queue, root);
+    }
+
+    if opts.loc {
+        loc_queue.retain(|(idx, _)| !arena[*idx].excluded);
+        if let Some(cache) = cache.as_deref_mut() {
+            loc_queue.retain(|(idx, path)| {
+                let (Some(stamp), Some(rel)) = (arena[*idx].stamp, rel_key(root, path)) else {
+                    return true;
+                };
+                let Some(counts) = cache.loc(rel, stamp) else {
+                    return true;
+                };
+                cache.keep_loc(rel, stamp, counts);
+                let lang = file_name_os(path).to_str().and_then(loc::language_of);
+                arena[*idx].loc = lang.map(|l| (l, counts));
+                false
+            });
+        }
+        let paths: Vec<PathBuf> = loc_queue.iter().map(|(_, p)| p.clone()).collect();
+        let counts = loc::count_all(&paths);
+        for ((idx, path), c) in loc_queue.into_iter().zip(counts) {
+            if let (Some(cache), Some((_, counts)), Some(stamp), Some(rel)) = (
+                cache.as_deref_mut(),
+                c,
+                arena[idx].stamp,
+                rel_key(root, &path),
+            ) {
+                cache.keep_loc(rel, stamp, counts);
+            }
+            arena[idx].loc = c;
+        }
+    }
+
+    if let Some(algo) = opts.hash {
+        hash_queue.retain(|(idx, _)| !arena[*idx].excluded);
+        if let Some(cache) = cache.as_deref_mut() {
+            hash_queue.retain(|(idx, path)| {
+                let (Some(stamp), Some(rel)) = (arena[*idx].stamp, rel_key(root, path)) else {
+                    return true;
+                };
+                let Some(hex) = cache.digest(rel, stamp).map(str::to_string) else {
+                    return true;
+                };
+                cache.keep_digest(rel, stamp, &hex);
+                arena[*idx].digest = Some(Digest {
+                    algo: algo.name(),
+                    hex,
+                });
+                false
+            });
+        }
+        let paths: Vec<PathBuf> = hash_queue.iter().map(|(_, p)| p.clone()).collect();
+        let digests = hash::hash_all(&paths, algo);
+        for ((idx, path), d) in hash_queue.into_iter().zip(digests) {
+            if let (Some(cache), Some(d), Some(stamp), Some(rel)) = (
+                cache.as_deref_mut(),
+                &d,
+                arena[idx].stamp,
+                rel_key(root, &path),
+            ) {
+                cache.keep_digest(rel, stamp, &d.hex);
+            }
+            arena[idx].digest = d;
+        }
+    }
+
+    Ok(materialize(root_idx, &arena, opts.has_file_filters()))
+}
+
+/// An entry yielded by the walker, or replayed from the scan cache.
+struct Entry {
+    path: PathBuf,
+    depth: usize,
+    kind: Kind,
+    /// Metadata of files, and of directories when caching.
+    meta: Option<Metadata>,
+}
+
+/// Lists entries with the ignore rules, filters and depth limit of a walk.
+struct Lister<'a> {
+    opts: &'a WalkOptions,
+    types: Option<Types>,
+    focus: Option<Focus>,
+    max_depth: Option<usize>,
+}
+
+impl Lister<'_> {
+    /// Walks `dir`, found at `depth` below the root, down to `max_depth`.
+    /// Ignore files above `dir` still apply.
+    fn list(
+        &self,
+        dir: &Path,
+        depth: usize,
+        max_depth: Option<usize>,
+    ) -> impl Iterator<Item = Entry> {
+        let opts = self.opts;
+        let mut wb = builder(dir, opts);
+        if let Some(t) = &self.types {
+            wb.types(t.clone());
+        }
+        wb.max_depth(max_depth).filter_entry({
+            let include_hidden = opts.include_hidden;
+            let focus = self.focus.clone();
+            move |e: &DirEntry| {
+                if let Some(f) = &focus
+                    && !f.visits(e.path(), depth + e.depth())
+                {
+                    return false;
+                }
+                !(include_hidden && is_inside_dot_git(e))
+            }
+        });
+
+        wb.build().filter_map(Result::ok).filter_map(move |e| {
+            if e.depth() > 0 && should_skip(&e) {
+                return None;
+            }
+            let kind = match e.file_type() {
+                Some(ft) if ft.is_dir() => Kind::Dir,
+                Some(ft) if ft.is_file() => Kind::File,
+                _ => Kind::Other,
+            };
+            let meta = match kind {
+                Kind::Other => None,
+                _ => e.metadata().ok(),
+            };
+            Some(Entry {
+                depth: depth + e.depth(),
+                path: e.into_path(),
+                kind,
+                meta,
+            })
+        })
+    }
+
+    /// Lists the tree of `root` from the directory listings of `cache` that
+    /// are still current, listing the others again, and records them all in
+    /// `cache`.
+    fn replay(&self, root: &Path, cache: &mut ScanCache) -> Vec<Entry> {
+        let mut out = Vec::new();
+        if let Ok(meta) = fs::metadata(root) {
+            let dev = device(&meta);
+            self.replay_dir(root, root, 0, &meta, dev, true, cache, &mut out);
+        }
+        out
+    }
+
+    /// Lists the entries of `dir` into `out`, then the entries of its
+    /// subdirectories. Cached listings are only used while the ignore files
+    /// of every directory above are unchanged (`trusted`).
+    #[allow(clippy::too_many_arguments)]
+    fn replay_dir(
+        &self,
+        root: &Path,
+        dir: &Path,
+        depth: usize,
+        meta: &Metadata,
+        dev: Option<u64>,
+        trusted: bool,
+        cache: &mut ScanCache,
+        out: &mut Vec<Entry>,
+    ) {
+        if self.max_depth.is_some_and(|m| depth >= m) {
+            return;
+        }
+        // Like the walker, do not descend into other file systems.
+        if self.opts.one_file_system && depth > 0 && device(meta) != dev {
+            return;
+        }
+        let rel = rel_key(root, dir);
+        let stamp = Stamp::of(meta);
+        let ignores = ignore_stamps(dir);
+        let cached = match (rel, stamp) {
+            (Some(r), Some(s)) if trusted => cache.listing(r, s, &ignores),
+            _ => None,
+        };
+
+        let entries: Vec<Entry> = match cached {
+            Some(listed) => listed
+                .iter()
+                .map(|l| {
+                    let path = dir.join(&l.name);
+                    let meta = match l.kind {
+                        Kind::Other => None,
+                        _ => fs::symlink_metadata(&path).ok(),
+                    };
+                    Entry {
+                        path,
+                        depth: depth + 1,
+                        kind: l.kind,
+                        meta,
+                    }
+                })
+                .collect(),
+            None => self
+                .list(dir, depth, Some(1))
+                .filter(|e| e.depth > depth)
+                .collect(),
+        };
+
+        let trusted = trusted && rel.is_some_and(|r| cache.same_rules(r, &ignores));
+        if let (Some(rel), Some(stamp)) = (rel, stamp) {
+            let listed: Option<Vec<Listed>> = entries
+                .iter()
+                .map(|e| {
+                    Some(Listed {
+                        name: e.path.file_name()?.to_str()?.to_string(),
+                        kind: e.kind,
+                    })
+                })
+                .collect();
+            if let Some(listed) = listed {
+                cache.keep_dir(rel, stamp, ignores, listed);
+            }
+        }
+
+        for e in entries {
+            let sub = match (&e.kind, &e.meta) {
+                (Kind::Dir, Some(m)) => Some((e.path.clone(), m.clone())),
+                _ => None,
+            };
+            out.push(e);
+            if let Some((path, m)) = sub {
+                self.replay_dir(root, &path, depth + 1, &m, dev, trusted, cache, out);
             }
-            _ => continue,
         }
     }
+}
+
+#[cfg(unix)]
+fn device(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Walked files are regular files, so only executables get a type.
+#[cfg(unix)]
+fn file_type(meta: &Metadata) -> Option<FileType> {
+    use std::os::unix::fs::PermissionsExt;
+    (meta.permissions().mode() & 0o111 != 0).then_some(FileType::Executable)
+}
+
+#[cfg(not(unix))]
+fn file_type(_meta: &Metadata) -> Option<FileType> {
+    None
+}
+
+/// Modification time in whole seconds since the Unix epoch, negative before.
+fn mtime_secs(meta: &Metadata) -> Option<i64> {
+    let mtime = meta.modified().ok()?;
+    match mtime.duration_since(UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_secs()).ok(),
+        Err(e) => i64::try_from(e.duration().as_secs()).ok().map(|s| -s),
+    }
+}
+
+/// Path of an entry below the root, as the scan cache keys it.
+fn rel_key<'a>(root: &Path, path: &'a Path) -> Option<&'a str> {
+    path.strip_prefix(root).ok()?.to_str()
+}
+
+/// Lists an archive found during the walk, cut at `--depth` like the rest of
+/// the tree. Unreadable archives are shown as plain files.
+fn read_archive(path: &Path, depth: usize, opts: &WalkOptions) -> Option<Node> {
+    let mut node = match archive::read_tree(path) {
+        Ok(n) => n,
+        Err(e) => {
+            debug!("cannot list {}: {e}", path.display());
+            return None;
+        }
+    };
+    if let Some(max) = opts.depth {
+        archive::cut(&mut node, max.saturating_sub(depth), opts.depth_hint);
+    }
+    Some(node)
+}
+
+fn matches_file_filters(meta: Option<&Metadata>, opts: &WalkOptions) -> bool {
+    if !opts.has_file_filters() {
+        return true;
+    }
+    let Some(meta) = meta else {
+        return false;
+    };
+
+    let size = meta.len();
+    if opts.min_size.is_some_and(|min| size < min) || opts.max_size.is_some_and(|max| size > max) {
+        return false;
+    }
+
+    if opts.newer_than.is_some() || opts.older_than.is_some() {
+        let Ok(mtime) = meta.modified() else {
+            return false;
+        };
+        if opts.newer_than.is_some_and(|t| mtime <= t)
+            || opts.older_than.is_some_and(|t| mtime >= t)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// `--focus` targets. Directories on the way to a target are expanded,
+/// whatever their depth; other entries are only expanded down to `--depth`
+/// (or not at all without it) and counted on their parent below that.
+#[derive(Clone)]
+struct Focus {
+    root: PathBuf,
+    targets: Vec<PathBuf>,
+    limit: usize,
+}
+
+impl Focus {
+    fn new(root: &Path, opts: &WalkOptions) -> Option<Self> {
+        if opts.focus.is_empty() {
+            return None;
+        }
+        Some(Self {
+            root: root.to_path_buf(),
+            targets: opts.focus.clone(),
+            limit: opts.depth.unwrap_or(0),
+        })
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Whether `dir` is a target or one of their ancestors.
+    fn on_path(&self, dir: &Path) -> bool {
+        let rel = self.relative(dir);
+        self.targets.iter().any(|t| t.starts_with(rel))
+    }
 
-    Ok(materialize(root_idx, &arena))
+    fn is_target(&self, path: &Path) -> bool {
+        let rel = self.relative(path);
+        self.targets.iter().any(|t| t == rel)
+    }
+
+    /// Whether the entry is shown, rather than counted on its parent.
+    fn expands(&self, path: &Path, depth: usize) -> bool {
+        depth <= self.limit || path.parent().is_some_and(|p| self.on_path(p))
+    }
+
+    /// Whether the walker needs to yield the entry at all: shown entries and
+    /// the direct children of shown directories, which are counted.
+    fn visits(&self, path: &Path, depth: usize) -> bool {
+        depth == 0
+            || self.expands(path, depth)
+            || path.parent().is_some_and(|p| self.expands(p, depth - 1))
+    }
+}
+
+/// Resolves walked directories to absolute paths to match them against the
+/// mount table, which only lists absolute mount points.
+struct MountTable {
+    mounts: MountMap,
+    abs_root: Option<PathBuf>,
+}
+
+impl MountTable {
+    fn new(root: &Path) -> Self {
+        let mounts = mounts::read_mounts();
+        let abs_root = if mounts.is_empty() {
+            None
+        } else {
+            root.canonicalize().ok()
+        };
+        Self { mounts, abs_root }
+    }
+
+    fn lookup(&self, root: &Path, path: &Path) -> Option<SmolStr> {
+        let abs_root = self.abs_root.as_ref()?;
+        let rel = path.strip_prefix(root).ok()?;
+        self.mounts.get(&abs_root.join(rel)).cloned()
+    }
+}
+
+/// Walk of `dir` following the link, filesystem, hidden and ignore rules of
+/// `opts`.
+fn builder(dir: &Path, opts: &WalkOptions) -> WalkBuilder {
+    let mut wb = WalkBuilder::new(dir);
+    wb.follow_links(false)
+        .same_file_system(opts.one_file_system)
+        .hidden(!opts.include_hidden)
+        .git_ignore(opts.follow_gitignore)
+        .git_exclude(opts.follow_gitignore)
+        .git_global(opts.follow_gitignore)
+        .threads(0);
+    wb
+}
+
+/// Whether a walk of `root` reaches `rel`, a path below it, with the hidden
+/// and ignore rules of `opts`. File filters are left out, and so are paths
+/// that no longer exist.
+pub fn reaches(root: &Path, rel: &Path, opts: &WalkOptions) -> bool {
+    let mut dir = root.to_path_buf();
+    for name in rel.components() {
+        let next = dir.join(name);
+        let include_hidden = opts.include_hidden;
+        let listed = builder(&dir, opts)
+            .max_depth(Some(1))
+            .filter_entry(move |e| !(include_hidden && is_inside_dot_git(e)))
+            .build()
+            .filter_map(Result::ok)
+            .any(|e| e.depth() == 1 && e.path() == next);
+        if !listed {
+            return false;
+        }
+        dir = next;
+    }
+    true
 }
 
 fn should_skip(entry: &DirEntry) -> bool {
     entry.path().file_name().is_none()
 }
 
-fn file_name_str(path: &Path) -> String {
-    path.file_name()
-        .map(|s| s.to_string_lossy().into_owned())
-        .unwrap_or_default()
+fn file_name_os(path: &Path) -> &OsStr {
+    path.file_name().unwrap_or_default()
 }
 
-fn push_dir(arena: &mut Vec<TmpNode>, name: &str) -> usize {
+fn push_dir(arena: &mut Vec<TmpNode>, name: &OsStr) -> usize {
     let n = TmpNode {
-        name: name.to_string(),
+        name: name.to_os_string(),
         size: 0,
         is_dir: true,
         children: Vec::new(),
+        collapsed: 0,
+        filtered: 0,
+        mount: None,
+        matches: None,
+        excluded: false,
+        focused: false,
+        archive: None,
+        digest: None,
+        duplicates: None,
+        loc: None,
+        stamp: None,
+        mtime: None,
+        file_type: None,
     };
     arena.push(n);
     arena.len() - 1
 }
 
-fn push_file(arena: &mut Vec<TmpNode>, name: &str, size: u64) -> usize {
+fn push_file(arena: &mut Vec<TmpNode>, name: &OsStr, size: u64) -> usize {
     let n = TmpNode {
-        name: name.to_string(),
+        name: name.to_os_string(),
         size,
         is_dir: false,
         children: Vec::new(),
+        collapsed: 0,
+        filtered: 0,
+        mount: None,
+        matches: None,
+        excluded: false,
+        focused: false,
+        archive: None,
+        digest: None,
+        duplicates: None,
+        loc: None,
+        stamp: None,
+        mtime: None,
+        file_type: None,
     };
     arena.push(n);
     arena.len() - 1
@@ -159,31 +694,124 @@ fn ensure_dir_idx(
     let p = dir.parent().unwrap_or(root);
     let p_idx = ensure_dir_idx(p, nodes_by_path, arena, root, root_idx);
 
-    let name = file_name_str(dir);
-    let idx = push_dir(arena, &name);
+    let idx = push_dir(arena, file_name_os(dir));
     nodes_by_path.insert(dir.to_path_buf(), idx);
     push_child(p_idx, idx, arena);
     idx
 }
 
-fn materialize(idx: usize, arena: &Vec<TmpNode>) -> Node {
+/// Builds the `Node` tree. With `prune` set, directories left without any
+/// child (and not cut off by the depth limit) are dropped, and their
+/// filtered-out count is carried to the parent.
+fn materialize(idx: usize, arena: &Vec<TmpNode>, prune: bool) -> Node {
     let tmp = &arena[idx];
 
     if tmp.is_dir {
         let mut sorted_children = tmp.children.clone();
         sorted_children.sort_by(|&a, &b| arena[a].name.cmp(&arena[b].name));
 
-        let kids: Vec<Node> = sorted_children
-            .into_iter()
-            .map(|c| materialize(c, arena))
-            .collect();
+        let mut filtered = tmp.filtered;
+        let mut kids: Vec<Node> = Vec::with_capacity(sorted_children.len());
+        for c in sorted_children {
+            if arena[c].excluded {
+                filtered += 1;
+                continue;
+            }
+            let kid = materialize(c, arena, prune);
+            if prune
+                && kid.is_dir()
+                && kid.children_slice().is_empty()
+                && kid.meta.collapsed.is_none()
+            {
+                filtered += kid.meta.filtered.unwrap_or(0);
+                continue;
+            }
+            kids.push(kid);
+        }
 
-        Node::new_dir(&tmp.name, kids)
+        let loc = kids.iter().filter_map(|k| k.meta.loc).reduce(|mut sum, c| {
+            sum.add(&c);
+            sum
+        });
+        let mut node = Node::new_dir("", kids).with_os_name(&tmp.name);
+        node.meta.loc = loc;
+        if tmp.collapsed > 0 {
+            node.meta.collapsed = Some(tmp.collapsed);
+        }
+        if filtered > 0 {
+            node.meta.filtered = Some(filtered);
+        }
+        node.meta.mount = tmp.mount.clone();
+        mark_focused(&mut node, tmp);
+        node
+    } else if let Some(archive) = &tmp.archive {
+        let mut node = archive.clone();
+        node.meta.matches = tmp.matches.clone();
+        mark_focused(&mut node, tmp);
+        node
     } else {
-        Node::new_file(&tmp.name, tmp.size)
+        let mut node = Node::new_file("", tmp.size).with_os_name(&tmp.name);
+        node.meta.matches = tmp.matches.clone();
+        node.meta.digest = tmp.digest.clone();
+        node.meta.duplicates = tmp.duplicates.clone();
+        node.meta.mtime = tmp.mtime;
+        node.meta.file_type = tmp.file_type;
+        if let Some((lang, counts)) = tmp.loc {
+            node.meta.language = Some(lang.name);
+            node.meta.loc = Some(counts);
+        }
+        mark_focused(&mut node, tmp);
+        node
     }
 }
 
+/// Annotates the files that have copies with the paths of the others, and
+/// drops the rest. Groups are numbered in path order, as the walk order
+/// varies between runs.
+fn mark_duplicates(arena: &mut [TmpNode], mut files: Vec<(usize, PathBuf)>, root: &Path) {
+    files.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+    let sized: Vec<(PathBuf, u64)> = files
+        .iter()
+        .map(|(idx, p)| (p.clone(), arena[*idx].size))
+        .collect();
+    for (idx, _) in &files {
+        arena[*idx].excluded = true;
+    }
+    let rel = |p: &Path| {
+        let rel = p.strip_prefix(root).unwrap_or(p);
+        rel.as_os_str().as_encoded_bytes().to_vec()
+    };
+    for (n, group) in dupes::find_groups(&sized).into_iter().enumerate() {
+        // Keeping one copy frees the space of all the others.
+        let reclaimable = sized[group[0]].1 * (group.len() as u64 - 1);
+        for &i in &group {
+            let tmp = &mut arena[files[i].0];
+            tmp.excluded = false;
+            tmp.duplicates = Some(Duplicates {
+                group: n + 1,
+                others: group
+                    .iter()
+                    .filter(|&&j| j != i)
+                    .map(|&j| rel(&files[j].1))
+                    .collect(),
+                reclaimable,
+            });
+        }
+    }
+}
+
+fn mark_focused(node: &mut Node, tmp: &TmpNode) {
+    if tmp.focused {
+        node.meta.highlight = Some((0, node.name_bytes().len()));
+    }
+}
+
+/// Whether `entry` is inside a `.git` directory, which is shown but not
+/// walked.
+fn is_inside_dot_git(entry: &DirEntry) -> bool {
+    !is_dot_git_dir(entry) && has_dot_git_ancestor(entry.path())
+}
+
 fn is_dot_git_dir(entry: &DirEntry) -> bool {
     entry.file_type().map(|t| t.is_dir()).unwrap_or(false)
         && entry.path().file_name() == Some(OsStr::new(".git"))
@@ -3,18 +3,55 @@
 
 use crate::model::node::{GitState, Node};
 use git2::{Repository, Status, StatusOptions};
-use smol_str::SmolStr;
 use std::collections::HashMap;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub type GitMap = HashMap<SmolStr, GitState>;
+/// Repository-relative path bytes → state. Keyed by bytes so that names that
+/// are not valid UTF-8 still match.
+pub type GitMap = HashMap<Vec<u8>, GitState>;
 
-pub fn collect_git_states(root: &Path) -> GitMap {
-    let Ok(repo) = Repository::discover(root) else {
-        return HashMap::new();
-    };
+/// Statuses of the repositories seen so far, so that several roots in the
+/// same repository only pay for one status scan.
+#[derive(Default)]
+pub struct GitCache {
+    repos: HashMap<PathBuf, GitMap>,
+}
+
+impl GitCache {
+    /// Sets the Git state of the files of `node`, the tree walked from `root`.
+    pub fn enrich(&mut self, node: &mut Node, root: &Path) {
+        let Ok(repo) = Repository::discover(root) else {
+            return;
+        };
+        let Some(workdir) = repo.workdir().and_then(|w| w.canonicalize().ok()) else {
+            return;
+        };
+        let Some(rel) = root
+            .canonicalize()
+            .ok()
+            .and_then(|r| r.strip_prefix(&workdir).ok().map(Path::to_path_buf))
+        else {
+            return;
+        };
+
+        let git = self
+            .repos
+            .entry(workdir)
+            .or_insert_with(|| collect_git_states(&repo));
+        let mut buf = rel.as_os_str().as_encoded_bytes().to_vec();
+        match node.children.as_mut() {
+            Some(children) => {
+                for c in children {
+                    enrich_with_git(c, git, &mut buf);
+                }
+            }
+            None => node.meta.git = git.get(&buf).copied(),
+        }
+    }
+}
 
+pub fn collect_git_states(repo: &Repository) -> GitMap {
     // not a lot of case when you have more than 4096 files!
     let mut map = HashMap::with_capacity(4096);
     let mut opts = StatusOptions::new();
@@ -26,35 +63,33 @@ pub fn collect_git_states(root: &Path) -> GitMap {
 
     if let Ok(statuses) = repo.statuses(Some(&mut opts)) {
         for entry in statuses.iter() {
-            if let Some(path) = entry.path() {
-                let s = entry.status();
-                let state = match true {
-                    _ if s.contains(Status::WT_MODIFIED) => GitState::Modified,
-                    _ if s.intersects(index_mask) => GitState::Staged,
-                    _ if s.contains(Status::WT_NEW) => GitState::Untracked,
-                    _ if s.contains(Status::IGNORED) => GitState::Ignored,
-                    _ if s.contains(Status::WT_DELETED) => GitState::Deleted,
-                    _ => GitState::Clean,
-                };
-                map.insert(SmolStr::new(path), state);
-            }
+            let s = entry.status();
+            let state = match true {
+                _ if s.contains(Status::WT_MODIFIED) => GitState::Modified,
+                _ if s.intersects(index_mask) => GitState::Staged,
+                _ if s.contains(Status::WT_NEW) => GitState::Untracked,
+                _ if s.contains(Status::IGNORED) => GitState::Ignored,
+                _ if s.contains(Status::WT_DELETED) => GitState::Deleted,
+                _ => GitState::Clean,
+            };
+            map.insert(entry.path_bytes().to_vec(), state);
         }
     }
 
     map
 }
 
-pub fn enrich_with_git(node: &mut Node, git: &GitMap, buf: &mut String) {
+/// Sets the Git state of `node` and its descendants. `buf` holds the path of
+/// the parent directory, relative to the work tree.
+pub fn enrich_with_git(node: &mut Node, git: &GitMap, buf: &mut Vec<u8>) {
     let keep = buf.len();
     if !buf.is_empty() {
-        buf.push('/');
+        buf.push(b'/');
     }
-    buf.push_str(&node.name);
-
-    let lookup_key = buf.strip_prefix("./").unwrap_or(buf.as_str());
+    buf.extend_from_slice(node.name_bytes());
 
     if !node.is_dir()
-        && let Some(&state) = git.get(lookup_key)
+        && let Some(&state) = git.get(buf.as_slice())
     {
         node.meta.git = Some(state);
     }
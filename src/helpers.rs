@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use std::time::Duration;
+use std::{ffi::OsString, time::Duration};
 
 pub fn format_duration(d: Duration) -> String {
     if d.as_secs_f64() >= 1.0 {
@@ -15,11 +15,157 @@ pub fn format_duration(d: Duration) -> String {
     }
 }
 
+/// Groups digits by thousands with a space (`4 213`).
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(' ');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Human readable size with binary units (`1.2 GiB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Parses a size such as `512`, `50M`, `1.5GiB` or `10kb`. Plain suffixes
+/// are decimal (`k` = 1000) and `i` suffixes are binary (`ki` = 1024).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let value: f64 = num
+        .parse()
+        .map_err(|_| format!("invalid size '{s}': expected e.g. 512, 50M, 1.5GiB"))?;
+
+    let unit = unit.trim().to_ascii_lowercase();
+    let unit = unit.strip_suffix('b').unwrap_or(&unit);
+    let factor: u64 = match unit {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        "t" => 1_000_000_000_000,
+        "ki" => 1 << 10,
+        "mi" => 1 << 20,
+        "gi" => 1 << 30,
+        "ti" => 1 << 40,
+        _ => return Err(format!("invalid size unit in '{s}'")),
+    };
+    Ok((value * factor as f64) as u64)
+}
+
+/// Parses a duration such as `90s`, `15min`, `1h30m`, `2d` or `3w`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{s}': expected e.g. 90s, 15m, 1h30m, 2d");
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut secs: u64 = 0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (num, tail) = rest.split_at(split);
+        let value: u64 = num.parse().map_err(|_| invalid())?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let factor = match unit {
+            "s" | "sec" => 1,
+            "m" | "min" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return Err(invalid()),
+        };
+        secs = value
+            .checked_mul(factor)
+            .and_then(|v| secs.checked_add(v))
+            .ok_or_else(invalid)?;
+        rest = tail;
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Path bytes as found in a path list, the Git index, an archive or a
+/// manifest.
+#[cfg(unix)]
+pub fn os_string(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes.to_vec())
+}
+
+#[cfg(not(unix))]
+pub fn os_string(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::time::Duration;
 
+    #[test]
+    fn count_grouping() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(4_213), "4 213");
+        assert_eq!(format_count(1_234_567), "1 234 567");
+    }
+
+    #[test]
+    fn size_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(1_288_490_189), "1.2 GiB");
+    }
+
+    #[test]
+    fn size_parsing() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("50M"), Ok(50_000_000));
+        assert_eq!(parse_size("50mb"), Ok(50_000_000));
+        assert_eq!(parse_size("1.5GiB"), Ok(1_610_612_736));
+        assert_eq!(parse_size("10ki"), Ok(10_240));
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("10x").is_err());
+    }
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("15min"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5_400)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert_eq!(parse_duration("1w"), Ok(Duration::from_secs(604_800)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("2025-11-20").is_err());
+    }
+
     #[test]
     fn ns_range() {
         assert_eq!(format_duration(Duration::from_nanos(0)), "0ns");
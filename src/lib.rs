@@ -3,10 +3,14 @@
 
 pub mod cli;
 pub mod config;
+pub mod diff;
 pub mod fs_scan;
 pub mod git;
 pub mod helpers;
 pub mod logger;
 pub mod model;
 pub mod renderer;
+pub mod transform;
+pub mod verify;
 pub mod version;
+pub mod watch;
@@ -3,15 +3,24 @@
 
 use arbor::{
     cli::args,
-    config::OutputFormat,
-    fs_scan::walk,
-    git::{collect_git_states, enrich_with_git, write_git_branch},
+    config::{AppConfig, OutputFormat, WalkOptions},
+    diff,
+    fs_scan::{archive, cache::ScanCache, dupes, fromfile, tracked, types, walk},
+    git::{GitCache, write_git_branch},
     helpers, logger,
-    renderer::{count, json, stdout},
+    model::node::Node,
+    renderer::{count, json, manifest, stdout},
+    transform,
+    verify::{self, Manifest},
+    watch,
 };
 use clap::Parser;
 use log::{debug, error};
-use std::{path::PathBuf, time::Instant};
+use std::{
+    io::{self, Write},
+    path::Path,
+    time::Instant,
+};
 
 fn main() {
     let raw = args::Args::parse();
@@ -27,47 +36,194 @@ fn main() {
 
     debug!("Config loaded successfully: {:?}", config);
 
-    let current_dir: PathBuf = config.runtime.root;
-    debug!("Running STree in: {}", current_dir.display());
+    if let OutputFormat::TypeList = config.output {
+        if let Err(e) = types::write_type_list(std::io::stdout().lock(), &config.walk.types) {
+            error!("write error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if config.runtime.watch {
+        let root = &config.runtime.roots[0];
+        if let Err(e) = watch::run(&mut std::io::stdout().lock(), root, &config) {
+            error!("❌ - failed to watch {}! {e}", root.display());
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(d) = &config.runtime.diff {
+        match diff::run(&mut std::io::stdout().lock(), d, &config) {
+            Ok(tally) if tally.is_clean() => return,
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                error!(
+                    "❌ - failed to compare {} and {}! {e}",
+                    d.old.display(),
+                    d.new.display()
+                );
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let checks = match config.runtime.verify.as_deref().map(Manifest::read) {
+        None => None,
+        Some(Ok(m)) => Some(m),
+        Some(Err(e)) => {
+            error!("cannot read manifest: {e}");
+            std::process::exit(2);
+        }
+    };
 
     let t_start = Instant::now();
 
-    match walk::walk_path(&current_dir, &config.walk) {
-        Ok(mut node) => {
-            let mut out = std::io::stdout().lock();
-            let res = match config.output {
-                OutputFormat::Count => count::render(&mut out, &node),
-
-                OutputFormat::Json | OutputFormat::Tree => {
-                    if config.git.enabled {
-                        let git_states = collect_git_states(&current_dir);
-                        let mut buf = String::new();
-                        enrich_with_git(&mut node, &git_states, &mut buf);
-                    }
-                    match config.output {
-                        OutputFormat::Json => json::render(&mut out, &node),
-                        OutputFormat::Tree => {
-                            if config.git.show_branch {
-                                write_git_branch(&mut out, &current_dir);
-                            }
-                            stdout::render(&mut out, &node, &config.render)
-                        }
-                        _ => unreachable!(),
-                    }
+    let mut out = std::io::stdout().lock();
+    let mut git = GitCache::default();
+    let mut totals = count::Totals::default();
+    let mut tally = verify::Tally::default();
+    let mut copies = dupes::Summary::default();
+    let mut langs = count::Languages::default();
+    let mut json_roots = Vec::new();
+    let mut failed = false;
+
+    for (i, root) in config.runtime.roots.iter().enumerate() {
+        debug!("Running STree in: {}", root.display());
+
+        let scanned = match &config.walk.fromfile {
+            Some(list) => {
+                fromfile::read_tree(list, root, config.walk.stat).map(|n| cut(n, &config.walk))
+            }
+            None if config.walk.tracked => {
+                tracked::read_tree(root, config.walk.untracked).map(|n| cut(n, &config.walk))
+            }
+            None if root.is_file() && root.file_name().is_some_and(archive::is_archive) => {
+                archive::read_tree(root).map(|n| cut(n, &config.walk))
+            }
+            None if config.runtime.cache => walk_cached(root, &config.walk_for(i)),
+            None => walk::walk_path(root, &config.walk_for(i)),
+        };
+        let mut node = match scanned {
+            Ok(node) => node,
+            Err(e) => {
+                error!("❌ - failed to execute STree on {}! {e}", root.display());
+                failed = true;
+                continue;
+            }
+        };
+
+        if let OutputFormat::Json | OutputFormat::Tree | OutputFormat::Manifest = config.output {
+            if config.git.enabled {
+                git.enrich(&mut node, root);
+            }
+            if let Some(m) = &checks {
+                m.verify(&mut node, root, &mut tally);
+            }
+        }
+        transform::apply(&mut node, &config.transform);
+
+        match config.output {
+            OutputFormat::Count => {
+                totals.add(&node);
+                langs.add(&node);
+            }
+            OutputFormat::Json => json_roots.push(node),
+            OutputFormat::Tree => {
+                totals.add(&node);
+                copies.add(&node);
+                if let Err(e) = write_tree(&mut out, &node, root, i > 0, &config) {
+                    error!("write error: {e}");
+                    std::process::exit(1);
                 }
-            };
-            if let Err(e) = res {
-                error!("write error: {e}");
-                std::process::exit(1);
             }
+            OutputFormat::Manifest => {
+                if let Err(e) = manifest::render(&mut out, &node, root) {
+                    error!("write error: {e}");
+                    std::process::exit(1);
+                }
+            }
+            OutputFormat::TypeList => unreachable!(),
         }
-        Err(e) => {
-            error!("❌ - failed to execute STree on this directory! {e}");
-            std::process::exit(1);
+    }
+
+    let multiple = config.runtime.roots.len() > 1;
+    let res = match config.output {
+        OutputFormat::Count if config.walk.loc => count::write_totals(&mut out, &totals)
+            .and_then(|_| writeln!(out))
+            .and_then(|_| count::write_languages(&mut out, &langs)),
+        OutputFormat::Count => count::write_totals(&mut out, &totals),
+        OutputFormat::Json if multiple => json::render_many(&mut out, &json_roots),
+        OutputFormat::Json => json_roots
+            .first()
+            .map_or(Ok(()), |node| json::render(&mut out, node)),
+        OutputFormat::Tree if multiple => {
+            writeln!(out).and_then(|_| count::write_totals(&mut out, &totals))
+        }
+        _ => Ok(()),
+    };
+    let res = res.and_then(|_| match config.output {
+        OutputFormat::Tree if config.walk.duplicates => {
+            writeln!(out).and_then(|_| dupes::write_summary(&mut out, &copies))
+        }
+        _ => Ok(()),
+    });
+    let res = res.and_then(|_| match (&checks, config.output) {
+        (Some(_), OutputFormat::Tree) => {
+            writeln!(out).and_then(|_| verify::write_tally(&mut out, &tally))
         }
+        (Some(_), _) => verify::write_tally(io::stderr(), &tally),
+        (None, _) => Ok(()),
+    });
+    if let Err(e) = res {
+        error!("write error: {e}");
+        std::process::exit(1);
     }
 
     if config.runtime.measure_time {
         eprintln!("time: {}", helpers::format_duration(t_start.elapsed()));
     }
+    if failed || !tally.is_clean() {
+        std::process::exit(1);
+    }
+}
+
+/// Walks `root` with its scan cache, then writes the cache back. A cache that
+/// cannot be written only costs the next run its speed-up.
+fn walk_cached(root: &Path, opts: &WalkOptions) -> io::Result<Node> {
+    let Some(mut cache) = ScanCache::open(root, opts) else {
+        return walk::walk_path(root, opts);
+    };
+    let node = walk::walk_path_cached(root, opts, &mut cache)?;
+    let file = cache.path().to_path_buf();
+    if let Err(e) = cache.save() {
+        debug!("cannot write cache {}: {e}", file.display());
+    }
+    Ok(node)
+}
+
+/// Cuts a tree that was read rather than walked to `--depth`, like the walk
+/// would have.
+fn cut(mut node: Node, opts: &WalkOptions) -> Node {
+    if let Some(max) = opts.depth {
+        archive::cut(&mut node, max, opts.depth_hint);
+    }
+    node
+}
+
+/// Writes one tree, separated from the previous one by a blank line.
+fn write_tree<W: Write>(
+    out: &mut W,
+    node: &Node,
+    root: &Path,
+    separate: bool,
+    config: &AppConfig,
+) -> io::Result<()> {
+    if separate {
+        writeln!(out)?;
+    }
+    if config.git.show_branch {
+        write_git_branch(out, root);
+    }
+    stdout::render(out, node, &config.render)
 }
@@ -1,6 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
+use crate::helpers;
+use smol_str::SmolStr;
+use std::ffi::{OsStr, OsString};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Kind {
     Dir,
@@ -18,16 +22,153 @@ pub enum GitState {
     Deleted,
 }
 
+/// What a file entry is on disk when it is not a plain regular file, to
+/// color it like `ls` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Executable,
+    Symlink,
+}
+
+/// Summary of the children dropped by `--max-entries-per-dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Truncated {
+    pub dirs: usize,
+    pub files: usize,
+    pub size: u64,
+}
+
+impl Truncated {
+    pub fn count(&self) -> usize {
+        self.dirs + self.files
+    }
+}
+
+/// A line matched by `--grep`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchLine {
+    /// 1-based line number.
+    pub number: usize,
+    pub text: String,
+}
+
+/// Result of a content search on a file. `lines` is only filled with
+/// `--grep-lines`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ContentMatches {
+    pub count: usize,
+    pub lines: Vec<MatchLine>,
+}
+
+/// Content digest of a file, computed with `--hash`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    /// Algorithm name (`sha256`, `blake3`).
+    pub algo: &'static str,
+    /// Lowercase hexadecimal digest.
+    pub hex: String,
+}
+
+impl Digest {
+    /// First 12 hex digits, as shown in the tree.
+    pub fn short(&self) -> &str {
+        &self.hex[..self.hex.len().min(12)]
+    }
+}
+
+/// Result of comparing a file with a `--verify` manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyState {
+    Ok,
+    Changed,
+    /// Listed in the manifest but not found on disk.
+    Missing,
+    /// Found on disk but not listed in the manifest.
+    New,
+}
+
+/// Change seen by `--watch` since the previous frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    /// No longer on disk, shown for one frame.
+    Removed,
+}
+
+/// Code, comment and blank lines of a file, or of all the files of a known
+/// language below a directory, counted by `--loc`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineCounts {
+    pub code: u64,
+    pub comments: u64,
+    pub blanks: u64,
+}
+
+impl LineCounts {
+    pub fn add(&mut self, other: &LineCounts) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// Other copies of a file found by `--duplicates`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicates {
+    /// Group number, shared by all the copies of the same contents.
+    pub group: usize,
+    /// Path bytes of the other copies, relative to the root.
+    pub others: Vec<Vec<u8>>,
+    /// Bytes freed by keeping a single copy of the group.
+    pub reclaimable: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MetaData {
     pub kind: Kind,
     pub size: Option<u64>,
     pub git: Option<GitState>,
+    /// Number of entries below a directory that exist on disk but were not
+    /// expanded (e.g. cut off by `--depth`).
+    pub collapsed: Option<usize>,
+    pub truncated: Option<Truncated>,
+    /// Number of files below this directory hidden by the type, size and
+    /// modification time filters, by `--grep` or by `--duplicates`, excluding
+    /// those counted on subdirectories.
+    pub filtered: Option<usize>,
+    pub matches: Option<ContentMatches>,
+    /// Filesystem type when this directory is a mount point.
+    pub mount: Option<SmolStr>,
+    /// Byte range of the name matched by `--find`, or the whole name of a
+    /// `--focus` target.
+    pub highlight: Option<(usize, usize)>,
+    /// Unix mode bits, when known without a `stat` (Git index, archives).
+    pub mode: Option<u32>,
+    pub digest: Option<Digest>,
+    pub verify: Option<VerifyState>,
+    pub duplicates: Option<Duplicates>,
+    /// Line counts of a file, or their sum below a directory.
+    pub loc: Option<LineCounts>,
+    /// Language of a file whose lines are counted.
+    pub language: Option<&'static str>,
+    pub change: Option<Change>,
+    /// Modification time of a file, in seconds since the Unix epoch.
+    pub mtime: Option<i64>,
+    /// Set on walked executables, and on the executables and links of the
+    /// Git index and of archives.
+    pub file_type: Option<FileType>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node {
+    /// Display name. Lossy when the original name is not valid UTF-8.
     pub name: String,
+    /// Original name, only kept when it is not valid UTF-8.
+    pub raw_name: Option<OsString>,
+    /// Original path segments when several directories were merged into
+    /// this node by `--compact`.
+    pub segments: Option<Vec<String>>,
     pub meta: MetaData,
     pub children: Option<Vec<Node>>,
 }
@@ -36,10 +177,27 @@ impl Node {
     pub fn new_file(name: &str, size: u64) -> Self {
         Self {
             name: name.to_string(),
+            raw_name: None,
+            segments: None,
             meta: MetaData {
                 kind: Kind::File,
                 size: Some(size),
                 git: None,
+                collapsed: None,
+                truncated: None,
+                filtered: None,
+                matches: None,
+                mount: None,
+                highlight: None,
+                mode: None,
+                digest: None,
+                verify: None,
+                duplicates: None,
+                loc: None,
+                language: None,
+                change: None,
+                mtime: None,
+                file_type: None,
             },
             children: None,
         }
@@ -48,15 +206,60 @@ impl Node {
     pub fn new_dir(name: &str, children: Vec<Node>) -> Self {
         Self {
             name: name.to_string(),
+            raw_name: None,
+            segments: None,
             meta: MetaData {
                 kind: Kind::Dir,
                 size: None,
                 git: None,
+                collapsed: None,
+                truncated: None,
+                filtered: None,
+                matches: None,
+                mount: None,
+                highlight: None,
+                mode: None,
+                digest: None,
+                verify: None,
+                duplicates: None,
+                loc: None,
+                language: None,
+                change: None,
+                mtime: None,
+                file_type: None,
             },
             children: Some(children),
         }
     }
 
+    /// Sets the name from an `OsStr`, keeping the original bytes around when
+    /// they can't be represented as UTF-8.
+    pub fn with_os_name(mut self, name: &OsStr) -> Self {
+        match name.to_str() {
+            Some(s) => {
+                self.name = s.to_string();
+                self.raw_name = None;
+            }
+            None => {
+                self.name = name.to_string_lossy().into_owned();
+                self.raw_name = Some(name.to_os_string());
+            }
+        }
+        self
+    }
+
+    pub fn os_name(&self) -> &OsStr {
+        match &self.raw_name {
+            Some(raw) => raw,
+            None => OsStr::new(&self.name),
+        }
+    }
+
+    /// Original name bytes, used for Git lookups and quoting.
+    pub fn name_bytes(&self) -> &[u8] {
+        self.os_name().as_encoded_bytes()
+    }
+
     pub fn children_slice(&self) -> &[Node] {
         self.children.as_deref().unwrap_or(&[])
     }
@@ -64,6 +267,83 @@ impl Node {
     pub fn is_dir(&self) -> bool {
         self.meta.kind == Kind::Dir
     }
+
+    /// Size of a file, or the summed size of every file below a directory.
+    pub fn total_size(&self) -> u64 {
+        match &self.children {
+            Some(children) => children.iter().map(Node::total_size).sum(),
+            None => self.meta.size.unwrap_or(0),
+        }
+    }
+
+    /// Descendant at `rel`, a `/`-separated path below this node.
+    pub fn find(&self, rel: &[u8]) -> Option<&Node> {
+        let mut cur = self;
+        for part in rel.split(|&b| b == b'/').filter(|p| !p.is_empty()) {
+            cur = cur
+                .children_slice()
+                .iter()
+                .find(|c| c.name_bytes() == part)?;
+        }
+        Some(cur)
+    }
+
+    pub fn find_mut(&mut self, rel: &[u8]) -> Option<&mut Node> {
+        let mut cur = self;
+        for part in rel.split(|&b| b == b'/').filter(|p| !p.is_empty()) {
+            cur = cur
+                .children
+                .as_mut()?
+                .iter_mut()
+                .find(|c| c.name_bytes() == part)?;
+        }
+        Some(cur)
+    }
+
+    /// Descendant at `rel`, created along with its missing parent
+    /// directories when it does not exist, in name order. `mark` is applied to
+    /// every node created; created files have no size.
+    pub fn insert_path(&mut self, rel: &[u8], is_dir: bool, mark: impl Fn(&mut Node)) -> &mut Node {
+        let mut parts = rel
+            .split(|&b| b == b'/')
+            .filter(|p| !p.is_empty())
+            .peekable();
+        let mut cur = self;
+        while let Some(part) = parts.next() {
+            let last = parts.peek().is_none();
+            let children = cur.children.get_or_insert_with(Vec::new);
+            let pos = match children.iter().position(|c| c.name_bytes() == part) {
+                Some(pos) => pos,
+                None => {
+                    let name = &helpers::os_string(part);
+                    let mut n = if last && !is_dir {
+                        let mut n = Node::new_file("", 0).with_os_name(name);
+                        n.meta.size = None;
+                        n
+                    } else {
+                        Node::new_dir("", Vec::new()).with_os_name(name)
+                    };
+                    mark(&mut n);
+                    let at = children
+                        .iter()
+                        .position(|c| c.name_bytes() > part)
+                        .unwrap_or(children.len());
+                    children.insert(at, n);
+                    at
+                }
+            };
+            cur = &mut children[pos];
+        }
+        cur
+    }
+
+    /// Last path segment of the name, used for icon lookups on merged nodes.
+    pub fn leaf_name(&self) -> &str {
+        match &self.segments {
+            Some(segs) => segs.last().map(String::as_str).unwrap_or(&self.name),
+            None => &self.name,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -88,4 +368,55 @@ mod tests {
         assert!(dir.meta.size.is_none());
         assert_eq!(dir.children.unwrap(), vec![child]);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_name_is_preserved() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = OsStr::from_bytes(b"caf\xe9.txt");
+        let node = Node::new_file("", 1).with_os_name(raw);
+        assert_eq!(node.name, "caf\u{fffd}.txt");
+        assert_eq!(node.name_bytes(), b"caf\xe9.txt");
+
+        let node = Node::new_file("", 1).with_os_name(OsStr::new("ok.txt"));
+        assert_eq!(node.name, "ok.txt");
+        assert!(node.raw_name.is_none());
+        assert_eq!(node.name_bytes(), b"ok.txt");
+    }
+
+    #[test]
+    fn test_total_size() {
+        let dir = Node::new_dir(
+            "root",
+            vec![
+                Node::new_file("a", 3),
+                Node::new_dir("sub", vec![Node::new_file("b", 4)]),
+            ],
+        );
+        assert_eq!(dir.total_size(), 7);
+        assert_eq!(Node::new_file("c", 5).total_size(), 5);
+    }
+
+    #[test]
+    fn insert_path_creates_missing_parents_in_name_order() {
+        let mut root = Node::new_dir("root", vec![Node::new_file("a", 1), Node::new_file("z", 1)]);
+        let added = root.insert_path(b"m/n.txt", false, |n| n.meta.collapsed = Some(0));
+        assert_eq!(added.name, "n.txt");
+        assert_eq!(added.meta.size, None);
+
+        let names: Vec<&str> = root
+            .children_slice()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "m", "z"]);
+        let m = root.find(b"m").unwrap();
+        assert!(m.is_dir());
+        assert_eq!(m.meta.collapsed, Some(0));
+        assert!(root.find_mut(b"m/n.txt").is_some());
+        assert!(root.find(b"m/x").is_none());
+
+        root.insert_path(b"a", false, |_| panic!("already there"));
+    }
 }
@@ -1,8 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use crate::model::node::GitState;
+use crate::{cli::args::ColorMode, model::node::GitState};
 use phf::phf_map;
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, IsTerminal},
+};
 
 pub static EXT_COLORS: phf::Map<&'static str, &'static [u8]> = phf_map! {
     "rs"   => b"\x1b[38;5;216m",
@@ -33,19 +38,46 @@ pub static EXT_COLORS: phf::Map<&'static str, &'static [u8]> = phf_map! {
     "pdf"  => b"\x1b[38;5;174m",
 };
 
-const DIR_COLOR: &[u8] = b"\x1b[38;5;110m";
-const FILE_DEFAULT: &[u8] = b"\x1b[38;5;252m";
+pub const DIR_COLOR: &[u8] = b"\x1b[38;5;110m";
+pub const FILE_DEFAULT: &[u8] = b"\x1b[38;5;252m";
 pub const RESET: &[u8] = b"\x1b[0m";
+/// Reverse video, so `--find` matches and `--focus` targets stand out on
+/// top of any name color.
+pub const HIGHLIGHT: &[u8] = b"\x1b[7m";
+pub const HIGHLIGHT_OFF: &[u8] = b"\x1b[27m";
+
+/// Whether `mode` colors what is written to stdout.
+pub fn enabled(mode: ColorMode) -> bool {
+    decide(mode, io::stdout().is_terminal(), |k| env::var_os(k))
+}
+
+/// `Auto` colors a terminal, unless `NO_COLOR` is set, `CLICOLOR` is `0` or
+/// `TERM` is `dumb`. `CLICOLOR_FORCE` colors the output even when it is not
+/// a terminal, and wins over the others.
+fn decide(mode: ColorMode, is_tty: bool, var: impl Fn(&str) -> Option<OsString>) -> bool {
+    let set = |k| var(k).is_some_and(|v| !v.is_empty());
+    let is = |k, value: &str| var(k).is_some_and(|v| v == value);
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto if set("CLICOLOR_FORCE") && !is("CLICOLOR_FORCE", "0") => true,
+        ColorMode::Auto if set("NO_COLOR") || is("CLICOLOR", "0") || is("TERM", "dumb") => false,
+        ColorMode::Auto => is_tty,
+    }
+}
 
 #[inline]
 pub fn color_for_name(name: &str, is_dir: bool) -> &'static [u8] {
     if is_dir {
         return DIR_COLOR;
     }
-    match name.rsplit('.').next() {
-        Some(ext) => EXT_COLORS.get(ext).copied().unwrap_or(FILE_DEFAULT),
-        None => FILE_DEFAULT,
-    }
+    ext_color(name).unwrap_or(FILE_DEFAULT)
+}
+
+/// Color of the extension of `name` in the built-in table.
+#[inline]
+pub fn ext_color(name: &str) -> Option<&'static [u8]> {
+    EXT_COLORS.get(name.rsplit('.').next()?).copied()
 }
 
 const GIT_MARKERS: [(&[u8], &[u8]); 7] = [
@@ -58,6 +90,17 @@ const GIT_MARKERS: [(&[u8], &[u8]); 7] = [
     (b"\x1b[31m", b" \x1b[1m\xE2\x9C\x96\x1b[0m"),   // Deleted ✖
 ];
 
+/// The markers of `GIT_MARKERS` without their escape codes.
+const GIT_LABELS: [&[u8]; 7] = [
+    b"",
+    b" ~",
+    b" +",
+    b" ?",
+    b" (i)Hello",
+    b" \xE2\x86\x92",
+    b" \xE2\x9C\x96",
+];
+
 #[inline]
 fn git_idx(g: GitState) -> usize {
     match g {
@@ -76,3 +119,66 @@ pub fn git_marker(g: GitState) -> Option<(&'static [u8], &'static [u8])> {
     let (c, s) = GIT_MARKERS[git_idx(g)];
     if s.is_empty() { None } else { Some((c, s)) }
 }
+
+/// The Git marker of `g`, when colors are off.
+#[inline]
+pub fn git_label(g: GitState) -> Option<&'static [u8]> {
+    let s = GIT_LABELS[git_idx(g)];
+    if s.is_empty() { None } else { Some(s) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(is_tty: bool, vars: &[(&str, &str)]) -> bool {
+        decide(ColorMode::Auto, is_tty, |k| {
+            vars.iter()
+                .find(|(name, _)| *name == k)
+                .map(|(_, v)| OsString::from(v))
+        })
+    }
+
+    #[test]
+    fn auto_colors_a_terminal() {
+        assert!(auto(true, &[]));
+        assert!(!auto(false, &[]));
+        assert!(auto(true, &[("CLICOLOR", "1"), ("TERM", "xterm-256color")]));
+    }
+
+    #[test]
+    fn auto_follows_the_environment() {
+        assert!(!auto(true, &[("NO_COLOR", "1")]));
+        assert!(auto(true, &[("NO_COLOR", "")]));
+        assert!(!auto(true, &[("CLICOLOR", "0")]));
+        assert!(!auto(true, &[("TERM", "dumb")]));
+        assert!(auto(false, &[("CLICOLOR_FORCE", "1")]));
+        assert!(!auto(false, &[("CLICOLOR_FORCE", "0")]));
+        assert!(!auto(false, &[("CLICOLOR_FORCE", "")]));
+        assert!(auto(true, &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]));
+        assert!(auto(false, &[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")]));
+    }
+
+    #[test]
+    fn always_and_never_ignore_the_environment() {
+        let none = |_: &str| None;
+        let all = |k: &str| Some(OsString::from(if k == "TERM" { "dumb" } else { "1" }));
+        assert!(decide(ColorMode::Always, false, all));
+        assert!(decide(ColorMode::Always, false, none));
+        assert!(!decide(ColorMode::Never, true, none));
+        assert!(!decide(ColorMode::Never, true, all));
+    }
+
+    #[test]
+    fn git_labels_match_the_colored_markers() {
+        for g in [GitState::Modified, GitState::Untracked, GitState::Deleted] {
+            let (_, colored) = git_marker(g).unwrap();
+            let plain: Vec<u8> = String::from_utf8_lossy(colored)
+                .replace("\x1b[1m", "")
+                .replace("\x1b[0m", "")
+                .into_bytes();
+            assert_eq!(git_label(g).unwrap(), plain);
+        }
+        assert_eq!(git_label(GitState::Clean), None);
+    }
+}
@@ -1,32 +1,109 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use crate::model::node::{Kind, Node};
-use std::io;
+use crate::helpers;
+use crate::model::node::{Kind, LineCounts, Node};
+use std::{collections::BTreeMap, io};
+
+/// Directory, file and filtered-out file counts of one or more trees.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Totals {
+    pub files: usize,
+    pub dirs: usize,
+    pub filtered: usize,
+}
 
-pub fn render<W: io::Write>(mut w: W, root: &Node) -> io::Result<()> {
-    let mut nb_files: usize = 0;
-    let mut nb_dirs: usize = 0;
+impl Totals {
+    pub fn add(&mut self, root: &Node) {
+        walk(root, &mut self.files, &mut self.dirs, &mut self.filtered);
+    }
+}
 
-    walk(root, &mut nb_files, &mut nb_dirs);
+pub fn render<W: io::Write>(w: W, root: &Node) -> io::Result<()> {
+    let mut totals = Totals::default();
+    totals.add(root);
+    write_totals(w, &totals)
+}
 
-    writeln!(
+pub fn write_totals<W: io::Write>(mut w: W, t: &Totals) -> io::Result<()> {
+    write!(
         w,
         "\u{f115} Directories: {} | \u{f016} Files: {}",
-        nb_dirs, nb_files
+        t.dirs, t.files
+    )?;
+    if t.filtered > 0 {
+        write!(w, " | \u{f0b0} Filtered out: {}", t.filtered)?;
+    }
+    writeln!(w)?;
+    Ok(())
+}
+
+/// Files and `--loc` line counts per language, over one or more trees.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Languages {
+    by_name: BTreeMap<&'static str, (usize, LineCounts)>,
+}
+
+impl Languages {
+    pub fn add(&mut self, n: &Node) {
+        if let (Some(lang), Some(c)) = (n.meta.language, n.meta.loc) {
+            let entry = self.by_name.entry(lang).or_default();
+            entry.0 += 1;
+            entry.1.add(&c);
+        }
+        for child in n.children_slice() {
+            self.add(child);
+        }
+    }
+}
+
+/// Writes one row per language, the largest code bases first, and a total.
+pub fn write_languages<W: io::Write>(mut w: W, langs: &Languages) -> io::Result<()> {
+    let mut rows: Vec<(&str, usize, LineCounts)> = langs
+        .by_name
+        .iter()
+        .map(|(name, (files, c))| (*name, *files, *c))
+        .collect();
+    rows.sort_by(|a, b| b.2.code.cmp(&a.2.code).then(a.0.cmp(b.0)));
+    let mut total = LineCounts::default();
+    let mut files = 0;
+    for (_, f, c) in &rows {
+        files += f;
+        total.add(c);
+    }
+    rows.push(("Total", files, total));
+
+    writeln!(
+        w,
+        "{:<12} {:>8} {:>10} {:>10} {:>10}",
+        "Language", "Files", "Code", "Comments", "Blanks"
     )?;
+    for (name, files, c) in rows {
+        writeln!(
+            w,
+            "{:<12} {:>8} {:>10} {:>10} {:>10}",
+            name,
+            helpers::format_count(files),
+            helpers::format_count(c.code as usize),
+            helpers::format_count(c.comments as usize),
+            helpers::format_count(c.blanks as usize)
+        )?;
+    }
     Ok(())
 }
 
-fn walk(n: &Node, nb_files: &mut usize, nb_dirs: &mut usize) {
+fn walk(n: &Node, nb_files: &mut usize, nb_dirs: &mut usize, nb_filtered: &mut usize) {
     match n.meta.kind {
         Kind::File => *nb_files += 1,
         Kind::Dir => *nb_dirs += 1,
     };
+    // A `--compact` chain holds one directory per segment before the last.
+    *nb_dirs += n.segments.as_ref().map_or(0, |s| s.len() - 1);
+    *nb_filtered += n.meta.filtered.unwrap_or(0);
 
     if let Some(children) = &n.children {
         for child in children {
-            walk(child, nb_files, nb_dirs);
+            walk(child, nb_files, nb_dirs, nb_filtered);
         }
     }
 }
@@ -73,6 +150,95 @@ mod tests {
         assert_eq!(s(&buf), expected);
     }
 
+    #[test]
+    fn reports_filtered_out_files() {
+        let mut sub = Node::new_dir("sub", vec![Node::new_file("big.bin", 3)]);
+        sub.meta.filtered = Some(2);
+        let mut root = Node::new_dir("root", vec![sub]);
+        root.meta.filtered = Some(5);
+
+        let mut buf: Vec<u8> = Vec::new();
+        render(&mut buf, &root).unwrap();
+
+        let expected = format!(
+            "\u{f115} Directories: {} | \u{f016} Files: {} | \u{f0b0} Filtered out: {}\n",
+            2, 1, 7
+        );
+        assert_eq!(s(&buf), expected);
+    }
+
+    #[test]
+    fn counts_the_directories_of_compacted_chains() {
+        let sub = Node::new_dir("b", vec![Node::new_file("x.rs", 1)]);
+        let mut root = Node::new_dir(
+            "root",
+            vec![Node::new_dir("a", vec![sub]), Node::new_dir("c", vec![])],
+        );
+        let before = {
+            let mut t = Totals::default();
+            t.add(&root);
+            t
+        };
+        crate::transform::compact::compact(&mut root, true);
+        let mut after = Totals::default();
+        after.add(&root);
+        assert_eq!(before, after);
+        assert_eq!((after.dirs, after.files), (4, 1));
+    }
+
+    #[test]
+    fn languages_table_sums_files_per_language() {
+        let file = |name: &str, lang, code| {
+            let mut f = Node::new_file(name, 1);
+            f.meta.language = Some(lang);
+            f.meta.loc = Some(LineCounts {
+                code,
+                comments: 1,
+                blanks: 2,
+            });
+            f
+        };
+        let root = Node::new_dir(
+            "root",
+            vec![
+                file("a.py", "Python", 10),
+                file("b.rs", "Rust", 1200),
+                file("c.rs", "Rust", 30),
+                Node::new_file("logo.png", 1),
+            ],
+        );
+        let mut langs = Languages::default();
+        langs.add(&root);
+
+        let mut buf: Vec<u8> = Vec::new();
+        write_languages(&mut buf, &langs).unwrap();
+        let expected = "\
+Language        Files       Code   Comments     Blanks
+Rust                2      1 230          2          4
+Python              1         10          1          2
+Total               3      1 240          3          6
+";
+        assert_eq!(s(&buf), expected);
+    }
+
+    #[test]
+    fn totals_add_up_several_roots() {
+        let mut totals = Totals::default();
+        totals.add(&Node::new_dir("src", vec![Node::new_file("a.rs", 1)]));
+        totals.add(&Node::new_dir(
+            "docs",
+            vec![Node::new_dir("img", vec![]), Node::new_file("b.md", 1)],
+        ));
+        assert_eq!(
+            totals,
+            Totals {
+                files: 2,
+                dirs: 3,
+                filtered: 0
+            }
+        );
+    }
+
     #[test]
     fn counts_when_root_is_file() {
         let root = Node::new_file("lonely.txt", 1);
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, Write},
+};
+
+use crate::cli::args::ColorDepth;
+
+/// Colors a terminal can show, the poorest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Depth {
+    Ansi16,
+    Ansi256,
+    #[default]
+    Truecolor,
+}
+
+/// The depth `--color-depth` asks for, or none for `none`.
+pub fn resolve(arg: ColorDepth) -> Option<Depth> {
+    match arg {
+        ColorDepth::Auto => Some(detect(|k| env::var_os(k))),
+        ColorDepth::Truecolor => Some(Depth::Truecolor),
+        ColorDepth::Ansi256 => Some(Depth::Ansi256),
+        ColorDepth::Ansi16 => Some(Depth::Ansi16),
+        ColorDepth::None => None,
+    }
+}
+
+/// Truecolor when `COLORTERM` says so or `TERM` is a direct-color one, 256
+/// colors for `TERM`s that have them, and otherwise, as on unknown terminals
+/// and in CI logs, the 16 basic colors.
+fn detect(var: impl Fn(&str) -> Option<OsString>) -> Depth {
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return Depth::Truecolor;
+    }
+    let term = var("TERM").unwrap_or_default();
+    let term = term.to_string_lossy();
+    if term.ends_with("-direct") || term.contains("truecolor") || term.contains("24bit") {
+        Depth::Truecolor
+    } else if term.contains("256color") {
+        Depth::Ansi256
+    } else {
+        Depth::Ansi16
+    }
+}
+
+/// Writes the SGR escape sequence `seq`, with its 24-bit and 256-color
+/// colors replaced by the closest ones `depth` has. Sequences arbor cannot
+/// read are written as they are.
+pub fn write_sgr<W: Write>(w: &mut W, seq: &[u8], depth: Depth) -> io::Result<()> {
+    let params = seq
+        .strip_prefix(b"\x1b[")
+        .and_then(|s| s.strip_suffix(b"m"))
+        .filter(|_| depth < Depth::Truecolor);
+    let Some(params) = params else {
+        return w.write_all(seq);
+    };
+    let mut nums = [0u16; 16];
+    let mut len = 0;
+    for p in params.split(|&b| b == b';') {
+        let n = std::str::from_utf8(p).ok().and_then(|p| p.parse().ok());
+        match (n, nums.get_mut(len)) {
+            (Some(n), Some(slot)) => *slot = n,
+            _ => return w.write_all(seq),
+        }
+        len += 1;
+    }
+
+    w.write_all(b"\x1b[")?;
+    let mut rest = &nums[..len];
+    let mut first = true;
+    while let Some((&p, tail)) = rest.split_first() {
+        if !first {
+            w.write_all(b";")?;
+        }
+        first = false;
+        // 38 and 48 start a foreground or background color: `5;N` or
+        // `2;R;G;B`.
+        let color = match (p, tail) {
+            (38 | 48, [5, n, ..]) if *n < 256 => {
+                rest = &tail[2..];
+                Color::Index(*n as u8)
+            }
+            (38 | 48, [2, r, g, b, ..]) => {
+                rest = &tail[4..];
+                Color::Rgb([*r, *g, *b].map(|c| c.min(255) as u8))
+            }
+            _ => {
+                write!(w, "{p}")?;
+                rest = tail;
+                continue;
+            }
+        };
+        let bg = p == 48;
+        match (depth, color) {
+            (Depth::Ansi256, Color::Index(n)) => write!(w, "{p};5;{n}")?,
+            (Depth::Ansi256, Color::Rgb(rgb)) => write!(w, "{p};5;{}", rgb_to_256(rgb))?,
+            (_, Color::Index(n)) => write_basic(w, nearest_basic(index_to_rgb(n)), bg)?,
+            (_, Color::Rgb(rgb)) => write_basic(w, nearest_basic(rgb), bg)?,
+        }
+    }
+    w.write_all(b"m")
+}
+
+enum Color {
+    Index(u8),
+    Rgb([u8; 3]),
+}
+
+/// Writes one of the 16 basic colors as a foreground or background.
+fn write_basic<W: Write>(w: &mut W, i: u8, bg: bool) -> io::Result<()> {
+    let base = if bg { 40 } else { 30 };
+    match i {
+        0..8 => write!(w, "{}", base + i),
+        _ => write!(w, "{}", base + 60 + i - 8),
+    }
+}
+
+/// The xterm values of the 16 basic colors.
+const BASIC: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// Levels of the 6×6×6 color cube of the 256-color palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn index_to_rgb(n: u8) -> [u8; 3] {
+    match n {
+        0..16 => BASIC[n as usize],
+        16..232 => {
+            let i = n - 16;
+            [i / 36, i / 6 % 6, i % 6].map(|c| CUBE[c as usize])
+        }
+        _ => [8 + (n - 232) * 10; 3],
+    }
+}
+
+fn rgb_to_256(rgb: [u8; 3]) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| CUBE[i].abs_diff(c))
+            .unwrap_or(0) as u8
+    };
+    let [r, g, b] = rgb.map(level);
+    let cube = 16 + 36 * r + 6 * g + b;
+    let avg = (rgb.iter().map(|&c| c as u16).sum::<u16>() / 3) as u8;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23);
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|&n| distance(index_to_rgb(n), rgb))
+        .unwrap_or(cube)
+}
+
+/// Grays of `BASIC`, only picked for colors that are nearly gray, as they
+/// would otherwise win for most soft colors of the 256-color palette.
+const GRAYS: [u8; 4] = [0, 7, 8, 15];
+
+fn nearest_basic(rgb: [u8; 3]) -> u8 {
+    let max = rgb.iter().max().copied().unwrap_or(0);
+    let min = rgb.iter().min().copied().unwrap_or(0);
+    let colored = max - min >= 48;
+    (0..16u8)
+        .filter(|i| !colored || !GRAYS.contains(i))
+        .min_by_key(|&i| distance(BASIC[i as usize], rgb))
+        .unwrap_or(7)
+}
+
+/// Squared distance between two colors, weighted for how the eye sees them
+/// ("redmean").
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let rmean = (a[0] as i32 + b[0] as i32) / 2;
+    let [dr, dg, db] = [0, 1, 2].map(|i| a[i] as i32 - b[i] as i32);
+    (((512 + rmean) * dr * dr) / 256 + 4 * dg * dg + ((767 - rmean) * db * db) / 256) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sgr(seq: &[u8], depth: Depth) -> String {
+        let mut buf = Vec::new();
+        write_sgr(&mut buf, seq, depth).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn detect_with(vars: &[(&str, &str)]) -> Depth {
+        detect(|k| {
+            vars.iter()
+                .find(|(name, _)| *name == k)
+                .map(|(_, v)| OsString::from(v))
+        })
+    }
+
+    #[test]
+    fn detects_the_depth_from_colorterm_and_term() {
+        assert_eq!(detect_with(&[("COLORTERM", "truecolor")]), Depth::Truecolor);
+        assert_eq!(
+            detect_with(&[("COLORTERM", "24bit"), ("TERM", "xterm")]),
+            Depth::Truecolor
+        );
+        assert_eq!(detect_with(&[("TERM", "xterm-direct")]), Depth::Truecolor);
+        assert_eq!(detect_with(&[("TERM", "xterm-256color")]), Depth::Ansi256);
+        assert_eq!(detect_with(&[("TERM", "screen-256color")]), Depth::Ansi256);
+        assert_eq!(detect_with(&[("TERM", "xterm")]), Depth::Ansi16);
+        assert_eq!(detect_with(&[("TERM", "linux")]), Depth::Ansi16);
+        assert_eq!(detect_with(&[]), Depth::Ansi16);
+    }
+
+    #[test]
+    fn maps_colors_to_the_closest_of_the_palette() {
+        let rust = b"\x1b[1;38;2;222;165;132m";
+        assert_eq!(sgr(rust, Depth::Truecolor), "\x1b[1;38;2;222;165;132m");
+        assert_eq!(sgr(rust, Depth::Ansi256), "\x1b[1;38;5;180m");
+        assert_eq!(sgr(b"\x1b[38;2;255;0;0m", Depth::Ansi16), "\x1b[91m");
+        assert_eq!(sgr(b"\x1b[38;2;0;0;140m", Depth::Ansi16), "\x1b[34m");
+        assert_eq!(
+            sgr(b"\x1b[38;2;128;128;128m", Depth::Ansi256),
+            "\x1b[38;5;244m"
+        );
+        assert_eq!(sgr(b"\x1b[38;5;110m", Depth::Ansi256), "\x1b[38;5;110m");
+        assert_eq!(sgr(b"\x1b[38;5;110m", Depth::Ansi16), "\x1b[94m");
+        assert_eq!(sgr(b"\x1b[38;5;252m", Depth::Ansi16), "\x1b[37m");
+        assert_eq!(sgr(b"\x1b[48;5;196;4m", Depth::Ansi16), "\x1b[101;4m");
+        assert_eq!(sgr(b"\x1b[01;34m", Depth::Ansi16), "\x1b[1;34m");
+    }
+
+    #[test]
+    fn leaves_sequences_it_cannot_read_alone() {
+        for seq in [
+            &b"\x1b[38:5:208m"[..],
+            b"\x1b[0K",
+            b"plain",
+            b"\x1b[38;2;1m",
+        ] {
+            assert_eq!(sgr(seq, Depth::Ansi16).as_bytes(), seq);
+        }
+    }
+
+    #[test]
+    fn the_palette_round_trips() {
+        for n in 16..=255u8 {
+            assert_eq!(rgb_to_256(index_to_rgb(n)), n, "{n}");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::{
+    cli::args::Charset,
+    renderer::{
+        colors,
+        depth::{self, Depth},
+    },
+};
+
+/// What is drawn in front of entries, each `width` columns wide: the branch
+/// of an entry, the one of the last entry of a directory, and below them, the
+/// guide of a directory with entries left, or blanks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guides {
+    pub branch: String,
+    pub last: String,
+    pub pipe: String,
+    pub blank: String,
+}
+
+impl Guides {
+    /// Guides drawn with `charset`, or with `lines` unset, only spaces.
+    pub fn new(charset: Charset, width: usize, lines: bool) -> Self {
+        let blank = " ".repeat(width);
+        if !lines {
+            return Self {
+                branch: blank.clone(),
+                last: blank.clone(),
+                pipe: blank.clone(),
+                blank,
+            };
+        }
+        let (tee, corner, vertical, horizontal) = match charset {
+            Charset::Utf8 => ('├', '└', '│', '─'),
+            Charset::Ascii => ('|', '`', '|', '-'),
+            Charset::Rounded => ('├', '╰', '│', '─'),
+            Charset::Heavy => ('┣', '┗', '┃', '━'),
+            Charset::Dashed => ('├', '└', '┆', '╌'),
+        };
+        let arm = |start: char| {
+            let mut s = String::from(start);
+            s.extend(std::iter::repeat_n(horizontal, width.saturating_sub(2)));
+            s.push(' ');
+            s
+        };
+        let mut pipe = String::from(vertical);
+        pipe.push_str(&" ".repeat(width.saturating_sub(1)));
+        Self {
+            branch: arm(tee),
+            last: arm(corner),
+            pipe,
+            blank,
+        }
+    }
+
+    /// The guides of each tree level, colored in turn with `palette`, which
+    /// starts over once exhausted. Blanks are never colored.
+    pub fn levels(&self, palette: &[Vec<u8>], depth: Depth) -> Vec<Level> {
+        let plain = |s: &str| s.as_bytes().to_vec();
+        if palette.is_empty() || self.pipe == self.blank {
+            return vec![Level {
+                branch: plain(&self.branch),
+                last: plain(&self.last),
+                pipe: plain(&self.pipe),
+            }];
+        }
+        palette
+            .iter()
+            .map(|color| {
+                let paint = |s: &str| {
+                    let mut out = Vec::with_capacity(s.len() + 16);
+                    // Writing to a `Vec` cannot fail.
+                    let _ = depth::write_sgr(&mut out, color, depth);
+                    out.extend_from_slice(s.trim_end().as_bytes());
+                    out.extend_from_slice(colors::RESET);
+                    out.extend_from_slice(&s.as_bytes()[s.trim_end().len()..]);
+                    out
+                };
+                Level {
+                    branch: paint(&self.branch),
+                    last: paint(&self.last),
+                    pipe: paint(&self.pipe),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Guides {
+    fn default() -> Self {
+        Self::new(Charset::Utf8, 4, true)
+    }
+}
+
+/// Guides of one tree level, ready to be written.
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub branch: Vec<u8>,
+    pub last: Vec<u8>,
+    pub pipe: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charsets_and_widths() {
+        let g = Guides::new(Charset::Ascii, 4, true);
+        assert_eq!([&*g.branch, &*g.last, &*g.pipe], ["|-- ", "`-- ", "|   "]);
+        let g = Guides::new(Charset::Rounded, 3, true);
+        assert_eq!([&*g.branch, &*g.last, &*g.pipe], ["├─ ", "╰─ ", "│  "]);
+        let g = Guides::new(Charset::Heavy, 2, true);
+        assert_eq!([&*g.branch, &*g.last, &*g.pipe], ["┣ ", "┗ ", "┃ "]);
+        let g = Guides::new(Charset::Dashed, 6, false);
+        assert_eq!([&*g.branch, &*g.last, &*g.pipe], ["      "; 3]);
+        assert_eq!(Guides::default().branch, "├── ");
+    }
+
+    #[test]
+    fn levels_cycle_through_the_palette() {
+        let palette = [b"\x1b[31m".to_vec(), b"\x1b[32m".to_vec()];
+        let levels = Guides::default().levels(&palette, Depth::Ansi16);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].branch, "\x1b[31m├──\x1b[0m ".as_bytes());
+        assert_eq!(levels[1].pipe, "\x1b[32m│\x1b[0m   ".as_bytes());
+
+        let spaces = Guides::new(Charset::Utf8, 4, false);
+        assert_eq!(spaces.levels(&palette, Depth::Ansi16).len(), 1);
+        assert_eq!(Guides::default().levels(&[], Depth::Ansi16).len(), 1);
+    }
+}
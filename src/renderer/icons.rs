@@ -246,18 +246,25 @@ pub static EXTENSION_ICONS: phf::Map<&'static str, char> = phf_map! {
     "pub" => '\u{f0dd6}',
 };
 
+pub const DIR_DEFAULT: char = '\u{f115}';
+pub const FILE_DEFAULT: char = '\u{f016}';
+
 #[inline]
 pub fn dir_icon(name: &str) -> char {
-    DIR_ICONS.get(name).copied().unwrap_or('\u{f115}')
+    DIR_ICONS.get(name).copied().unwrap_or(DIR_DEFAULT)
 }
 
 #[inline]
 pub fn file_icon(name: &str) -> char {
-    match FILE_ICONS.get(name).copied() {
-        Some(c) => c,
-        None => match name.rsplit('.').next() {
-            Some(ext) => EXTENSION_ICONS.get(ext).copied().unwrap_or('\u{f016}'),
-            None => '\u{f016}',
-        },
-    }
+    FILE_ICONS
+        .get(name)
+        .copied()
+        .or_else(|| ext_icon(name))
+        .unwrap_or(FILE_DEFAULT)
+}
+
+/// Icon of the extension of `name` in the built-in table.
+#[inline]
+pub fn ext_icon(name: &str) -> Option<char> {
+    EXTENSION_ICONS.get(name.rsplit('.').next()?).copied()
 }
@@ -1,21 +1,148 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
-use crate::model::node::{GitState, Kind, Node};
+use crate::model::node::{
+    Change, ContentMatches, Duplicates, GitState, Kind, LineCounts, MatchLine, Node, Truncated,
+    VerifyState,
+};
 use serde::Serialize;
-use std::io;
+use std::{borrow::Cow, io};
 
 #[derive(Serialize)]
 struct JsonNode<'a> {
     name: &'a str,
+    /// Exact name bytes, only present when the name is not valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_bytes: Option<&'a [u8]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    segments: Option<Vec<&'a str>>,
     kind: &'static str,
     size: Option<u64>,
+    /// Modification time of a file, in seconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mtime: Option<i64>,
+    /// Permission bits in octal (`"0755"`), when known from an archive or
+    /// the Git index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     git: Option<&'static str>,
+    /// `--hash` digest, as `"<algo>:<hex>"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verify: Option<&'static str>,
+    /// State of the entry in `arbor diff`; unchanged entries have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'static str>,
+    /// `--loc` counts of a file, or their sum below a directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    loc: Option<JsonLoc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates: Option<JsonDuplicates<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mount: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collapsed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<JsonTruncated>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filtered: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<JsonMatches<'a>>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     children: Vec<JsonNode<'a>>,
 }
 
+#[derive(Serialize)]
+struct JsonTruncated {
+    count: usize,
+    dirs: usize,
+    files: usize,
+    size: u64,
+}
+
+impl From<Truncated> for JsonTruncated {
+    fn from(t: Truncated) -> Self {
+        JsonTruncated {
+            count: t.count(),
+            dirs: t.dirs,
+            files: t.files,
+            size: t.size,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonMatches<'a> {
+    count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lines: Vec<JsonMatchLine<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonMatchLine<'a> {
+    line: usize,
+    text: &'a str,
+}
+
+impl<'a> From<&'a ContentMatches> for JsonMatches<'a> {
+    fn from(m: &'a ContentMatches) -> Self {
+        JsonMatches {
+            count: m.count,
+            lines: m
+                .lines
+                .iter()
+                .map(|l: &'a MatchLine| JsonMatchLine {
+                    line: l.number,
+                    text: &l.text,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonLoc {
+    code: u64,
+    comments: u64,
+    blanks: u64,
+}
+
+impl From<LineCounts> for JsonLoc {
+    fn from(c: LineCounts) -> Self {
+        JsonLoc {
+            code: c.code,
+            comments: c.comments,
+            blanks: c.blanks,
+        }
+    }
+}
+
+/// A file of a `--duplicates` group, with the other paths of the group.
+#[derive(Serialize)]
+struct JsonDuplicates<'a> {
+    group: usize,
+    others: Vec<Cow<'a, str>>,
+    reclaimable: u64,
+}
+
+impl<'a> From<&'a Duplicates> for JsonDuplicates<'a> {
+    fn from(d: &'a Duplicates) -> Self {
+        JsonDuplicates {
+            group: d.group,
+            others: d
+                .others
+                .iter()
+                .map(|p| String::from_utf8_lossy(p))
+                .collect(),
+            reclaimable: d.reclaimable,
+        }
+    }
+}
+
 fn kind_as_str(k: Kind) -> &'static str {
     match k {
         Kind::Dir => "dir",
@@ -35,13 +162,52 @@ fn git_as_str(g: GitState) -> &'static str {
     }
 }
 
+fn change_as_str(c: Change) -> &'static str {
+    match c {
+        Change::Added => "added",
+        Change::Modified => "modified",
+        Change::Removed => "removed",
+    }
+}
+
+fn verify_as_str(v: VerifyState) -> &'static str {
+    match v {
+        VerifyState::Ok => "ok",
+        VerifyState::Changed => "changed",
+        VerifyState::Missing => "missing",
+        VerifyState::New => "new",
+    }
+}
+
 impl<'a> From<&'a Node> for JsonNode<'a> {
     fn from(n: &'a Node) -> Self {
         JsonNode {
             name: &n.name,
+            name_bytes: n.raw_name.as_ref().map(|_| n.name_bytes()),
+            segments: n
+                .segments
+                .as_ref()
+                .map(|s| s.iter().map(String::as_str).collect()),
             kind: kind_as_str(n.meta.kind),
             size: n.meta.size,
+            mtime: n.meta.mtime,
+            mode: n.meta.mode.map(|m| format!("{:04o}", m & 0o7777)),
             git: n.meta.git.map(git_as_str),
+            digest: n
+                .meta
+                .digest
+                .as_ref()
+                .map(|d| format!("{}:{}", d.algo, d.hex)),
+            verify: n.meta.verify.map(verify_as_str),
+            change: n.meta.change.map(change_as_str),
+            language: n.meta.language,
+            loc: n.meta.loc.map(JsonLoc::from),
+            duplicates: n.meta.duplicates.as_ref().map(JsonDuplicates::from),
+            mount: n.meta.mount.as_deref(),
+            collapsed: n.meta.collapsed,
+            truncated: n.meta.truncated.map(JsonTruncated::from),
+            filtered: n.meta.filtered,
+            matches: n.meta.matches.as_ref().map(JsonMatches::from),
             children: n.children_slice().iter().map(JsonNode::from).collect(),
         }
     }
@@ -52,6 +218,12 @@ pub fn render<W: io::Write>(w: W, root: &Node) -> io::Result<()> {
     serde_json::to_writer_pretty(w, &j).map_err(io::Error::other)
 }
 
+/// Writes several roots as a JSON array.
+pub fn render_many<W: io::Write>(w: W, roots: &[Node]) -> io::Result<()> {
+    let j: Vec<JsonNode> = roots.iter().map(JsonNode::from).collect();
+    serde_json::to_writer_pretty(w, &j).map_err(io::Error::other)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,6 +276,84 @@ mod tests {
         assert!(out.contains("\"git\": \"modified\""));
     }
 
+    #[test]
+    fn render_collapsed_count() {
+        let mut dir = Node::new_dir("src", vec![]);
+        dir.meta.collapsed = Some(7);
+        let mut buf = Vec::new();
+        render(&mut buf, &dir).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["collapsed"], 7);
+    }
+
+    #[test]
+    fn render_compacted_segments() {
+        let mut root = Node::new_dir(
+            "root",
+            vec![Node::new_dir("a", vec![Node::new_dir("b", vec![])])],
+        );
+        crate::transform::compact::compact(&mut root, false);
+        let mut buf = Vec::new();
+        render(&mut buf, &root).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert!(parsed.get("segments").is_none());
+        assert_eq!(parsed["children"][0]["name"], "a/b");
+        assert_eq!(parsed["children"][0]["segments"][0], "a");
+        assert_eq!(parsed["children"][0]["segments"][1], "b");
+    }
+
+    #[test]
+    fn render_truncated_summary() {
+        let mut dir = Node::new_dir("data", vec![Node::new_file("a", 1)]);
+        dir.meta.truncated = Some(Truncated {
+            dirs: 1,
+            files: 2,
+            size: 30,
+        });
+        let mut buf = Vec::new();
+        render(&mut buf, &dir).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["truncated"]["count"], 3);
+        assert_eq!(parsed["truncated"]["dirs"], 1);
+        assert_eq!(parsed["truncated"]["files"], 2);
+        assert_eq!(parsed["truncated"]["size"], 30);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn render_non_utf8_name_bytes() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let file = Node::new_file("", 1).with_os_name(OsStr::from_bytes(b"a\xffb"));
+        let mut buf = Vec::new();
+        render(&mut buf, &file).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["name"], "a\u{fffd}b");
+        assert_eq!(parsed["name_bytes"], serde_json::json!([97, 255, 98]));
+
+        let mut buf = Vec::new();
+        render(&mut buf, &Node::new_file("ok", 1)).unwrap();
+        assert!(!String::from_utf8(buf).unwrap().contains("name_bytes"));
+    }
+
+    #[test]
+    fn render_grep_matches() {
+        let mut file = Node::new_file("main.rs", 1);
+        file.meta.matches = Some(ContentMatches {
+            count: 1,
+            lines: vec![MatchLine {
+                number: 4,
+                text: "TODO".into(),
+            }],
+        });
+        let mut buf = Vec::new();
+        render(&mut buf, &file).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["matches"]["count"], 1);
+        assert_eq!(parsed["matches"]["lines"][0]["line"], 4);
+        assert_eq!(parsed["matches"]["lines"][0]["text"], "TODO");
+    }
+
     #[test]
     fn render_nested_empty_children_skipped() {
         let root = Node::new_dir("root", vec![Node::new_dir("empty", vec![])]);
@@ -126,4 +376,28 @@ mod tests {
         assert_eq!(parsed["children"][0]["name"], "a");
         assert_eq!(parsed["children"][0]["kind"], "file");
     }
+
+    #[test]
+    fn render_mode_as_octal_permissions() {
+        let mut file = Node::new_file("run.sh", 5);
+        file.meta.mode = Some(0o100755);
+        let mut buf = Vec::new();
+        render(&mut buf, &file).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed["mode"], "0755");
+    }
+
+    #[test]
+    fn render_many_writes_an_array() {
+        let roots = [
+            Node::new_dir("src", vec![Node::new_file("a.rs", 1)]),
+            Node::new_dir("docs", vec![]),
+        ];
+        let mut buf = Vec::new();
+        render_many(&mut buf, &roots).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(parsed[0]["name"], "src");
+        assert_eq!(parsed[0]["children"][0]["name"], "a.rs");
+        assert_eq!(parsed[1]["name"], "docs");
+    }
 }
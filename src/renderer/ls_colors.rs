@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::env;
+
+use crate::{
+    model::node::{FileType, Node},
+    renderer::colors,
+};
+
+/// File-type keys of `LS_COLORS`, in the order of `LsColors::kinds`.
+const KEYS: [&str; 4] = ["di", "fi", "ln", "ex"];
+const DI: usize = 0;
+const FI: usize = 1;
+const LN: usize = 2;
+const EX: usize = 3;
+
+/// Name colors read from `LS_COLORS` and `EZA_COLORS`, on top of the
+/// built-in table.
+#[derive(Debug, Clone, Default)]
+pub struct LsColors {
+    kinds: [Option<Vec<u8>>; KEYS.len()],
+    /// Escape sequence of each glob of `globs`, by index.
+    styles: Vec<Vec<u8>>,
+    globs: GlobSet,
+    /// Set by the `reset` entry of `EZA_COLORS`, which drops the built-in
+    /// table and what `LS_COLORS` set.
+    no_builtin: bool,
+}
+
+impl LsColors {
+    pub fn from_env() -> Self {
+        let ls = env::var("LS_COLORS").ok();
+        let eza = env::var("EZA_COLORS").ok();
+        Self::parse(ls.as_deref(), eza.as_deref())
+    }
+
+    /// Reads `ls` then `eza` entries, later entries winning over earlier
+    /// ones. Entries that are not file-type keys or name patterns, or whose
+    /// value is not a list of SGR parameters, are ignored.
+    pub fn parse(ls: Option<&str>, eza: Option<&str>) -> Self {
+        let mut no_builtin = false;
+        let mut kinds: Vec<(&str, Vec<u8>)> = Vec::new();
+        let mut rules: Vec<(Glob, Vec<u8>)> = Vec::new();
+        let entries = ls.into_iter().map(|s| (s, false));
+        for (spec, is_eza) in entries.chain(eza.map(|s| (s, true))) {
+            for entry in spec.split(':') {
+                if is_eza && entry == "reset" {
+                    no_builtin = true;
+                    kinds.clear();
+                    rules.clear();
+                    continue;
+                }
+                let Some((key, value)) = entry.split_once('=') else {
+                    continue;
+                };
+                if key.is_empty() || !is_sgr(value) {
+                    // `ln=target` colors links as their target, which is
+                    // what the name patterns do without an `ln` color.
+                    continue;
+                }
+                if KEYS.contains(&key) {
+                    kinds.push((key, escape(value)));
+                } else if key.len() == 2 && key.bytes().all(|b| b.is_ascii_lowercase()) {
+                    // Other two-letter keys are `ls` and `eza` settings
+                    // that do not apply to names.
+                } else if let Ok(glob) = GlobBuilder::new(key)
+                    // Like `ls`, extensions match whatever their case.
+                    .case_insensitive(key.starts_with("*.") && !has_glob_chars(&key[2..]))
+                    .build()
+                {
+                    rules.push((glob, escape(value)));
+                }
+            }
+        }
+        Self {
+            no_builtin,
+            ..Self::from_rules(kinds, rules)
+        }
+    }
+
+    /// Colors set by file-type key (`di`, `ex`...) and by name pattern, later
+    /// ones winning over earlier ones.
+    pub fn from_rules<'k>(
+        kinds: impl IntoIterator<Item = (&'k str, Vec<u8>)>,
+        rules: Vec<(Glob, Vec<u8>)>,
+    ) -> Self {
+        let mut out = Self::default();
+        for (key, style) in kinds {
+            if let Some(i) = KEYS.iter().position(|k| *k == key) {
+                out.kinds[i] = Some(style);
+            }
+        }
+        let mut builder = GlobSetBuilder::new();
+        for (glob, style) in rules {
+            builder.add(glob);
+            out.styles.push(style);
+        }
+        out.globs = builder.build().unwrap_or_default();
+        out
+    }
+
+    /// Escape sequence that starts the name of `node`, empty when nothing
+    /// colors it.
+    pub fn color(&self, node: &Node) -> &[u8] {
+        layered(&[self], self.keeps_builtin(), node)
+    }
+
+    /// Whether the built-in colors still apply, which the `reset` entry of
+    /// `EZA_COLORS` turns off.
+    pub fn keeps_builtin(&self) -> bool {
+        !self.no_builtin
+    }
+
+    fn pattern(&self, name: &str) -> Option<&[u8]> {
+        let i = self.globs.matches(name).into_iter().max()?;
+        Some(&self.styles[i])
+    }
+
+    fn file_type(&self, ft: FileType) -> Option<&[u8]> {
+        let key = match ft {
+            FileType::Executable => EX,
+            FileType::Symlink => LN,
+        };
+        self.kind(key)
+    }
+
+    fn kind(&self, i: usize) -> Option<&[u8]> {
+        self.kinds[i].as_deref()
+    }
+}
+
+/// Escape sequence that starts the name of `node`, from the first of
+/// `layers` that colors it, most specific rules first: the file-type keys,
+/// then the name patterns, then `fi`. With `builtin`, the built-in colors
+/// come under the layers, and otherwise names no layer colors are left
+/// uncolored.
+pub fn layered<'a>(layers: &[&'a LsColors], builtin: bool, node: &Node) -> &'a [u8] {
+    let first = |f: &dyn Fn(&'a LsColors) -> Option<&'a [u8]>| layers.iter().copied().find_map(f);
+    if node.is_dir() {
+        return match first(&|l| l.kind(DI)) {
+            Some(c) => c,
+            None if builtin => colors::DIR_COLOR,
+            None => b"",
+        };
+    }
+    if let Some(ft) = file_type(node)
+        && let Some(c) = first(&|l| l.file_type(ft))
+    {
+        return c;
+    }
+    let name = node.leaf_name();
+    if let Some(c) = first(&|l| l.pattern(name)) {
+        return c;
+    }
+    if builtin && let Some(c) = colors::ext_color(name) {
+        return c;
+    }
+    match first(&|l| l.kind(FI)) {
+        Some(c) => c,
+        None if builtin => colors::FILE_DEFAULT,
+        None => b"",
+    }
+}
+
+/// The type of a file: the one found by the walk, or for entries of the Git
+/// index and of archives, the one of their mode bits.
+fn file_type(node: &Node) -> Option<FileType> {
+    if node.meta.file_type.is_some() {
+        return node.meta.file_type;
+    }
+    let mode = node.meta.mode?;
+    if mode & 0o170000 == 0o120000 {
+        Some(FileType::Symlink)
+    } else if mode & 0o111 != 0 {
+        Some(FileType::Executable)
+    } else {
+        None
+    }
+}
+
+/// Whether `value` only holds SGR parameters (`01;34`), so that nothing
+/// else is ever written to the terminal.
+fn is_sgr(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit() || b == b';')
+}
+
+fn has_glob_chars(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+fn escape(sgr: &str) -> Vec<u8> {
+    format!("\x1b[{sgr}m").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(name: &str, ft: FileType) -> Node {
+        let mut n = Node::new_file(name, 1);
+        n.meta.file_type = Some(ft);
+        n
+    }
+
+    #[test]
+    fn file_type_keys_win_over_extensions() {
+        let c = LsColors::parse(
+            Some("di=01;34:ln=01;36:or=31;01:pi=33:so=01;35:ex=01;32:*.rs=33"),
+            None,
+        );
+        assert_eq!(c.color(&Node::new_dir("src", vec![])), b"\x1b[01;34m");
+        assert_eq!(c.color(&Node::new_file("main.rs", 1)), b"\x1b[33m");
+        assert_eq!(
+            c.color(&typed("run.rs", FileType::Executable)),
+            b"\x1b[01;32m"
+        );
+        assert_eq!(c.color(&typed("l", FileType::Symlink)), b"\x1b[01;36m");
+
+        let mut tracked = Node::new_file("build.sh", 1);
+        tracked.meta.mode = Some(0o100755);
+        assert_eq!(c.color(&tracked), b"\x1b[01;32m");
+    }
+
+    #[test]
+    fn later_patterns_win_and_extensions_ignore_case() {
+        let c = LsColors::parse(Some("*.md=35:*README.md=01;33:Makefile=32"), None);
+        assert_eq!(c.color(&Node::new_file("NOTES.MD", 1)), b"\x1b[35m");
+        assert_eq!(c.color(&Node::new_file("README.md", 1)), b"\x1b[01;33m");
+        assert_eq!(c.color(&Node::new_file("Makefile", 1)), b"\x1b[32m");
+    }
+
+    #[test]
+    fn falls_back_to_the_builtin_table() {
+        let c = LsColors::parse(Some("*.md=35:ln=target:rs=0:no=00"), None);
+        let builtin = LsColors::default();
+        for n in [
+            Node::new_dir("src", vec![]),
+            Node::new_file("main.rs", 1),
+            Node::new_file("LICENSE", 1),
+            typed("l.rs", FileType::Symlink),
+        ] {
+            assert_eq!(c.color(&n), colors::color_for_name(&n.name, n.is_dir()));
+            assert_eq!(builtin.color(&n), c.color(&n));
+        }
+        let c = LsColors::parse(Some("fi=37"), None);
+        assert_eq!(c.color(&Node::new_file("main.rs", 1)), b"\x1b[38;5;216m");
+        assert_eq!(c.color(&Node::new_file("LICENSE", 1)), b"\x1b[37m");
+    }
+
+    #[test]
+    fn eza_colors_override_ls_colors() {
+        let c = LsColors::parse(Some("di=34:*.rs=33"), Some("*.rs=31:ur=1;33"));
+        assert_eq!(c.color(&Node::new_dir("src", vec![])), b"\x1b[34m");
+        assert_eq!(c.color(&Node::new_file("main.rs", 1)), b"\x1b[31m");
+
+        let c = LsColors::parse(Some("di=34:*.rs=33"), Some("reset:*.md=35"));
+        assert_eq!(c.color(&Node::new_dir("src", vec![])), b"");
+        assert_eq!(c.color(&Node::new_file("main.rs", 1)), b"");
+        assert_eq!(c.color(&Node::new_file("a.md", 1)), b"\x1b[35m");
+    }
+
+    #[test]
+    fn ignores_values_that_are_not_sgr_parameters() {
+        let c = LsColors::parse(Some("di=\x1b]0;pwned\x07:*.rs=31m:*.md=[35"), None);
+        assert!(c.kind(DI).is_none());
+        assert!(c.styles.is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::model::node::Node;
+use std::{
+    io::{self, Write},
+    path::{Component, Path},
+};
+
+/// Writes one `<digest>  <path>` line per shown file that has a digest, in
+/// the format of `sha256sum` (and `b3sum`), so that `sha256sum -c` can check
+/// it. Paths are the root path joined with the names in the tree.
+pub fn render<W: Write>(mut w: W, root: &Node, root_path: &Path) -> io::Result<()> {
+    let mut buf = path_prefix(root_path);
+    for c in root.children_slice() {
+        write_node(&mut w, c, &mut buf)?;
+    }
+    Ok(())
+}
+
+/// Path bytes the entries below `root` are listed under: empty for `.`, the
+/// root without its leading `./` otherwise.
+pub fn path_prefix(root: &Path) -> Vec<u8> {
+    let mut comps = root.components();
+    let rest = match comps.next() {
+        Some(Component::CurDir) => comps.as_path(),
+        _ => root,
+    };
+    rest.as_os_str().as_encoded_bytes().to_vec()
+}
+
+/// Appends `name` to the path in `buf`, with a `/` separator when needed.
+pub fn push_name(buf: &mut Vec<u8>, name: &[u8]) {
+    if !buf.is_empty() && !buf.ends_with(b"/") {
+        buf.push(b'/');
+    }
+    buf.extend_from_slice(name);
+}
+
+fn write_node<W: Write>(w: &mut W, node: &Node, buf: &mut Vec<u8>) -> io::Result<()> {
+    let keep = buf.len();
+    push_name(buf, node.name_bytes());
+    if let Some(d) = &node.meta.digest {
+        write_line(w, &d.hex, buf)?;
+    }
+    for c in node.children_slice() {
+        write_node(w, c, buf)?;
+    }
+    buf.truncate(keep);
+    Ok(())
+}
+
+/// Writes a manifest line. Like GNU coreutils, a path holding a backslash or
+/// a line break is escaped and the line starts with a backslash.
+fn write_line<W: Write>(w: &mut W, hex: &str, path: &[u8]) -> io::Result<()> {
+    if !path.iter().any(|b| matches!(b, b'\\' | b'\n' | b'\r')) {
+        w.write_all(hex.as_bytes())?;
+        w.write_all(b"  ")?;
+        w.write_all(path)?;
+        return w.write_all(b"\n");
+    }
+    w.write_all(b"\\")?;
+    w.write_all(hex.as_bytes())?;
+    w.write_all(b"  ")?;
+    for &b in path {
+        match b {
+            b'\\' => w.write_all(b"\\\\")?,
+            b'\n' => w.write_all(b"\\n")?,
+            b'\r' => w.write_all(b"\\r")?,
+            _ => w.write_all(&[b])?,
+        }
+    }
+    w.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::Digest;
+
+    fn hashed(name: &str, hex: &str) -> Node {
+        let mut n = Node::new_file(name, 1);
+        n.meta.digest = Some(Digest {
+            algo: "sha256",
+            hex: hex.to_string(),
+        });
+        n
+    }
+
+    fn out(root: &Node, path: &str) -> String {
+        let mut buf = Vec::new();
+        render(&mut buf, root, Path::new(path)).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn lists_files_with_root_relative_paths() {
+        let root = Node::new_dir(
+            ".",
+            vec![
+                Node::new_dir("src", vec![hashed("main.rs", "aa")]),
+                hashed("README.md", "bb"),
+                Node::new_file("unreadable", 1),
+            ],
+        );
+        assert_eq!(out(&root, "."), "aa  src/main.rs\nbb  README.md\n");
+        assert_eq!(
+            out(&root, "./dist"),
+            "aa  dist/src/main.rs\nbb  dist/README.md\n"
+        );
+    }
+
+    #[test]
+    fn escapes_like_coreutils() {
+        let root = Node::new_dir(".", vec![hashed("a\\b\nc", "cc")]);
+        assert_eq!(out(&root, "."), "\\cc  a\\\\b\\nc\n");
+    }
+}
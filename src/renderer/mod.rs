@@ -3,6 +3,12 @@
 
 pub mod colors;
 pub mod count;
+pub mod depth;
+pub mod guides;
 pub mod icons;
 pub mod json;
+pub mod ls_colors;
+pub mod manifest;
+pub mod quote;
 pub mod stdout;
+pub mod theme;
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::cli::args::QuotingStyle;
+use std::io::{self, Write};
+
+/// Characters that make a name need quoting in a POSIX shell.
+const SHELL_SPECIAL: &[char] = &[
+    ' ', '\t', '!', '"', '#', '$', '&', '\'', '(', ')', '*', ';', '<', '>', '?', '[', '\\', ']',
+    '^', '`', '{', '|', '}', '~',
+];
+
+/// Writes a file name using one of the GNU `ls --quoting-style` conventions.
+///
+/// - `Literal`: raw bytes, no escaping at all.
+/// - `Escape`: control characters, backslashes and invalid UTF-8 bytes as
+///   C escapes (`\n`, `\\`, `\351`), everything else as is.
+/// - `Shell`: as is when safe, `'single quoted'` when the name contains shell
+///   metacharacters, `$'ANSI-C quoted'` when it contains unprintable bytes.
+/// - `C`: a double-quoted C string literal.
+pub fn write_name<W: Write>(w: &mut W, name: &[u8], style: QuotingStyle) -> io::Result<()> {
+    match style {
+        QuotingStyle::Literal => w.write_all(name),
+        QuotingStyle::Escape => write_escaped(w, name, false),
+        QuotingStyle::C => {
+            w.write_all(b"\"")?;
+            write_escaped(w, name, true)?;
+            w.write_all(b"\"")
+        }
+        QuotingStyle::Shell => write_shell(w, name),
+    }
+}
+
+fn needs_escape(c: char) -> bool {
+    c.is_control() || c == '\\'
+}
+
+fn write_escaped<W: Write>(w: &mut W, name: &[u8], quote: bool) -> io::Result<()> {
+    for chunk in name.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if needs_escape(c) || (quote && c == '"') {
+                write_escaped_char(w, c)?;
+            } else {
+                let mut buf = [0u8; 4];
+                w.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+            }
+        }
+        for b in chunk.invalid() {
+            write!(w, "\\{b:03o}")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_escaped_char<W: Write>(w: &mut W, c: char) -> io::Result<()> {
+    let named: &[u8] = match c {
+        '\x07' => b"\\a",
+        '\x08' => b"\\b",
+        '\t' => b"\\t",
+        '\n' => b"\\n",
+        '\x0b' => b"\\v",
+        '\x0c' => b"\\f",
+        '\r' => b"\\r",
+        '\\' => b"\\\\",
+        '"' => b"\\\"",
+        '\'' => b"\\'",
+        _ => b"",
+    };
+    if !named.is_empty() {
+        return w.write_all(named);
+    }
+    let mut buf = [0u8; 4];
+    for b in c.encode_utf8(&mut buf).bytes() {
+        write!(w, "\\{b:03o}")?;
+    }
+    Ok(())
+}
+
+fn write_shell<W: Write>(w: &mut W, name: &[u8]) -> io::Result<()> {
+    let printable = match std::str::from_utf8(name) {
+        Ok(s) if !s.chars().any(char::is_control) => Some(s),
+        _ => None,
+    };
+
+    match printable {
+        Some(s) if !s.is_empty() && !s.contains(SHELL_SPECIAL) => w.write_all(name),
+        Some(s) => {
+            w.write_all(b"'")?;
+            w.write_all(s.replace('\'', "'\\''").as_bytes())?;
+            w.write_all(b"'")
+        }
+        None => {
+            w.write_all(b"$'")?;
+            for chunk in name.utf8_chunks() {
+                for c in chunk.valid().chars() {
+                    if needs_escape(c) || c == '\'' {
+                        write_escaped_char(w, c)?;
+                    } else {
+                        let mut buf = [0u8; 4];
+                        w.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+                    }
+                }
+                for b in chunk.invalid() {
+                    write!(w, "\\{b:03o}")?;
+                }
+            }
+            w.write_all(b"'")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quoted(name: &[u8], style: QuotingStyle) -> String {
+        let mut buf = Vec::new();
+        write_name(&mut buf, name, style).unwrap();
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+
+    #[test]
+    fn plain_names_are_untouched() {
+        for style in [
+            QuotingStyle::Literal,
+            QuotingStyle::Escape,
+            QuotingStyle::Shell,
+        ] {
+            assert_eq!(quoted("héllo_wörld.rs".as_bytes(), style), "héllo_wörld.rs");
+        }
+        assert_eq!(quoted(b"main.rs", QuotingStyle::C), "\"main.rs\"");
+    }
+
+    #[test]
+    fn escape_neutralizes_control_characters() {
+        assert_eq!(quoted(b"a\nb", QuotingStyle::Escape), "a\\nb");
+        assert_eq!(quoted(b"\x1b[31mred", QuotingStyle::Escape), "\\033[31mred");
+        assert_eq!(
+            quoted(b"back\\slash", QuotingStyle::Escape),
+            "back\\\\slash"
+        );
+        assert_eq!(quoted(b"with space", QuotingStyle::Escape), "with space");
+    }
+
+    #[test]
+    fn escape_keeps_invalid_bytes_as_octal() {
+        assert_eq!(quoted(b"caf\xe9", QuotingStyle::Escape), "caf\\351");
+    }
+
+    #[test]
+    fn literal_writes_raw_bytes() {
+        let mut buf = Vec::new();
+        write_name(&mut buf, b"a\nb\xff", QuotingStyle::Literal).unwrap();
+        assert_eq!(buf, b"a\nb\xff");
+    }
+
+    #[test]
+    fn shell_quotes_only_when_needed() {
+        assert_eq!(quoted(b"plain.txt", QuotingStyle::Shell), "plain.txt");
+        assert_eq!(quoted(b"with space", QuotingStyle::Shell), "'with space'");
+        assert_eq!(quoted(b"it's", QuotingStyle::Shell), "'it'\\''s'");
+        assert_eq!(quoted(b"a\nb", QuotingStyle::Shell), "$'a\\nb'");
+        assert_eq!(quoted(b"x\xff'", QuotingStyle::Shell), "$'x\\377\\''");
+    }
+
+    #[test]
+    fn c_style_is_a_string_literal() {
+        assert_eq!(quoted(b"say \"hi\"", QuotingStyle::C), "\"say \\\"hi\\\"\"");
+        assert_eq!(quoted(b"tab\there", QuotingStyle::C), "\"tab\\there\"");
+    }
+}
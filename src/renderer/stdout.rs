@@ -2,94 +2,259 @@
 // Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
 
 use crate::{
-    cli::args::ColorMode,
+    cli::args::QuotingStyle,
     config::RenderOptions,
-    model::node::Node,
-    renderer::{colors, icons},
+    helpers,
+    model::node::{Change, Node, Truncated, VerifyState},
+    renderer::{
+        colors,
+        depth::{self, Depth},
+        guides::Level,
+        quote,
+        theme::Theme,
+    },
 };
 use std::io::{self, Write};
 
-type NameFn<W> = fn(&mut W, &Node) -> io::Result<()>;
+type NameFn<W> = for<'a> fn(&mut W, &Node, NameStyle<'a>) -> io::Result<()>;
+
+/// How entries are written: the quoting style, the theme, whether
+/// highlighted ranges (`--find`, `--focus`) are shown, the width of the
+/// `--loc` columns in front of names, and the guides of each tree level.
+#[derive(Debug, Clone, Copy)]
+struct NameStyle<'a> {
+    quoting: QuotingStyle,
+    theme: &'a Theme,
+    depth: Depth,
+    highlight: bool,
+    loc_width: Option<usize>,
+    levels: &'a [Level],
+    blank: &'a [u8],
+}
 
 pub fn render<W: Write>(mut w: W, root: &Node, opts: &RenderOptions) -> io::Result<()> {
     let write_name: NameFn<W> = match (opts.icons, opts.color, opts.git) {
-        (false, ColorMode::Never, false) => write_plain,
-        (false, ColorMode::Auto, false) => write_plain,
-        (false, ColorMode::Always, false) => write_plain_full,
-        (true, ColorMode::Never, false) => write_icon_plain,
-        (true, ColorMode::Auto, false) => write_icon_plain,
-        (true, ColorMode::Always, false) => write_icon_full,
-        (false, ColorMode::Never, true) => write_plain_git,
-        (false, ColorMode::Auto, true) => write_plain_gitonly,
-        (false, ColorMode::Always, true) => write_full_git,
-        (true, ColorMode::Never, true) => write_icon_plain_git,
-        (true, ColorMode::Auto, true) => write_icon_git,
-        (true, ColorMode::Always, true) => write_icon_full_git,
+        (false, false, false) => write_plain,
+        (false, true, false) => write_plain_full,
+        (true, false, false) => write_icon_plain,
+        (true, true, false) => write_icon_full,
+        (false, false, true) => write_plain_git,
+        (false, true, true) => write_full_git,
+        (true, false, true) => write_icon_plain_git,
+        (true, true, true) => write_icon_full_git,
+    };
+    let palette = if opts.color {
+        opts.theme.guide_colors()
+    } else {
+        &[]
+    };
+    let levels = opts.guides.levels(palette, opts.depth);
+    let q = NameStyle {
+        quoting: opts.quoting,
+        theme: &opts.theme,
+        depth: opts.depth,
+        highlight: opts.color,
+        loc_width: opts.loc.then(|| loc_width(root)),
+        levels: &levels,
+        blank: opts.guides.blank.as_bytes(),
     };
-    write_name(&mut w, root)?;
+    write_loc(&mut w, root, q)?;
+    write_digest(&mut w, root)?;
+    write_name(&mut w, root, q)?;
+    write_hints(&mut w, root, q)?;
     w.write_all(b"\n")?;
-    let children = root.children_slice();
-    let last_idx = children.len().saturating_sub(1);
-    for (i, child) in children.iter().enumerate() {
-        render_node(&mut w, child, "", i == last_idx, write_name)?;
-    }
-    Ok(())
+    render_children(&mut w, root, b"", 0, write_name, q)
 }
 
 fn render_node<W: Write>(
     w: &mut W,
     node: &Node,
-    prefix: &str,
+    prefix: &[u8],
+    level: usize,
     is_last: bool,
     write_name: NameFn<W>,
+    q: NameStyle,
 ) -> io::Result<()> {
-    let branch = if is_last { "└── " } else { "├── " };
-    w.write_all(prefix.as_bytes())?;
-    w.write_all(branch.as_bytes())?;
-    write_name(w, node)?;
+    let guides = &q.levels[level % q.levels.len()];
+    w.write_all(prefix)?;
+    w.write_all(if is_last {
+        &guides.last
+    } else {
+        &guides.branch
+    })?;
+    write_loc(w, node, q)?;
+    write_digest(w, node)?;
+    write_name(w, node, q)?;
+    write_hints(w, node, q)?;
     w.write_all(b"\n")?;
-    let mut new_prefix = String::with_capacity(prefix.len() + 4);
-    new_prefix.push_str(prefix);
-    new_prefix.push_str(if is_last { "    " } else { "│   " });
+    let mut new_prefix = Vec::with_capacity(prefix.len() + guides.pipe.len());
+    new_prefix.extend_from_slice(prefix);
+    new_prefix.extend_from_slice(if is_last { q.blank } else { &guides.pipe });
+    write_match_lines(w, node, &new_prefix)?;
+    render_children(w, node, &new_prefix, level + 1, write_name, q)
+}
+
+/// Prints the `--grep-lines` output under a file, aligned on its children.
+fn write_match_lines<W: Write>(w: &mut W, node: &Node, prefix: &[u8]) -> io::Result<()> {
+    let Some(m) = &node.meta.matches else {
+        return Ok(());
+    };
+    for line in &m.lines {
+        w.write_all(prefix)?;
+        write!(w, "  {}: ", line.number)?;
+        quote::write_name(w, line.text.as_bytes(), QuotingStyle::Escape)?;
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn render_children<W: Write>(
+    w: &mut W,
+    node: &Node,
+    prefix: &[u8],
+    level: usize,
+    write_name: NameFn<W>,
+    q: NameStyle,
+) -> io::Result<()> {
     let children = node.children_slice();
+    let truncated = node.meta.truncated;
     let last_idx = children.len().saturating_sub(1);
     for (i, child) in children.iter().enumerate() {
-        render_node(w, child, &new_prefix, i == last_idx, write_name)?;
+        let is_last = i == last_idx && truncated.is_none();
+        render_node(w, child, prefix, level, is_last, write_name, q)?;
+    }
+    if let Some(t) = truncated {
+        w.write_all(prefix)?;
+        w.write_all(&q.levels[level % q.levels.len()].last)?;
+        write_truncated(w, &t)?;
+        w.write_all(b"\n")?;
     }
     Ok(())
 }
 
+fn write_truncated<W: Write>(w: &mut W, t: &Truncated) -> io::Result<()> {
+    let mut parts = Vec::with_capacity(3);
+    match t.dirs {
+        0 => {}
+        1 => parts.push(String::from("1 dir")),
+        n => parts.push(format!("{} dirs", helpers::format_count(n))),
+    }
+    match t.files {
+        0 => {}
+        1 => parts.push(String::from("1 file")),
+        n => parts.push(format!("{} files", helpers::format_count(n))),
+    }
+    parts.push(helpers::format_size(t.size));
+    write!(
+        w,
+        "… and {} more ({})",
+        helpers::format_count(t.count()),
+        parts.join(", ")
+    )
+}
+
+/// Width of the `--loc` columns: the root holds the largest counts.
+fn loc_width(root: &Node) -> usize {
+    let c = root.meta.loc.unwrap_or_default();
+    c.code.max(c.comments).max(c.blanks).to_string().len()
+}
+
+/// Writes the code, comment and blank line counts, or blanks of the same
+/// width for entries without any.
 #[inline]
-fn write_plain<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    w.write_all(n.name.as_bytes())?;
-    if n.is_dir() {
-        w.write_all(b"/")?;
+fn write_loc<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    let Some(width) = q.loc_width else {
+        return Ok(());
+    };
+    match n.meta.loc {
+        Some(c) => write!(
+            w,
+            "[{:>width$} {:>width$} {:>width$}] ",
+            c.code, c.comments, c.blanks
+        ),
+        None => write!(w, "{:1$}", "", width * 3 + 5),
     }
-    Ok(())
 }
 
+/// Writes the start of the `--hash` digest in front of a file name.
 #[inline]
-fn write_plain_full<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    w.write_all(colors::color_for_name(&n.name, n.is_dir()))?;
-    w.write_all(n.name.as_bytes())?;
-    if n.is_dir() {
-        w.write_all(b"/")?;
+fn write_digest<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
+    match &n.meta.digest {
+        Some(d) => write!(w, "[{}] ", d.short()),
+        None => Ok(()),
     }
-    w.write_all(colors::RESET)?;
-    Ok(())
 }
 
 #[inline]
-fn write_icon_plain<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    let icon = if n.is_dir() {
-        icons::dir_icon(&n.name)
-    } else {
-        icons::file_icon(&n.name)
+fn write_hints<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    if let Some(fs) = &n.meta.mount {
+        write!(w, " [{fs}]")?;
+    }
+    match n.meta.change {
+        Some(Change::Added) => w.write_all(b" (added)")?,
+        Some(Change::Modified) => w.write_all(b" (modified)")?,
+        Some(Change::Removed) => w.write_all(b" (removed)")?,
+        None => {}
+    }
+    match n.meta.verify {
+        Some(VerifyState::Changed) => w.write_all(b" (changed)")?,
+        Some(VerifyState::Missing) => w.write_all(b" (missing)")?,
+        Some(VerifyState::New) => w.write_all(b" (new)")?,
+        Some(VerifyState::Ok) | None => {}
+    }
+    match n.meta.matches.as_ref().map(|m| m.count) {
+        Some(1) => w.write_all(b" (1 match)")?,
+        Some(count) => write!(w, " ({count} matches)")?,
+        None => {}
+    }
+    if let Some(d) = &n.meta.duplicates {
+        w.write_all(b" (= ")?;
+        for (i, path) in d.others.iter().enumerate() {
+            if i > 0 {
+                w.write_all(b", ")?;
+            }
+            quote::write_name(w, path, q.quoting)?;
+        }
+        write!(w, "; {} reclaimable)", helpers::format_size(d.reclaimable))?;
+    }
+    match n.meta.collapsed {
+        Some(1) => w.write_all(" (… 1 entry)".as_bytes()),
+        Some(count) => write!(w, " (… {count} entries)"),
+        None => Ok(()),
+    }
+}
+
+/// Writes the quoted name, with its highlighted range in reverse video. Quoting
+/// styles that wrap the name in quotes get the whole name highlighted, as
+/// quoting the pieces separately would change the output.
+fn write_label<W: Write>(w: &mut W, n: &Node, s: NameStyle) -> io::Result<()> {
+    let name = n.name_bytes();
+    let Some((start, end)) = n
+        .meta
+        .highlight
+        .filter(|&(_, end)| s.highlight && end <= name.len())
+    else {
+        return quote::write_name(w, name, s.quoting);
     };
-    let mut buf = [0u8; 4];
-    w.write_all(icon.encode_utf8(&mut buf).as_bytes())?;
-    w.write_all(b" ")?;
-    w.write_all(n.name.as_bytes())?;
+    match s.quoting {
+        QuotingStyle::Literal | QuotingStyle::Escape => {
+            quote::write_name(w, &name[..start], s.quoting)?;
+            w.write_all(colors::HIGHLIGHT)?;
+            quote::write_name(w, &name[start..end], s.quoting)?;
+            w.write_all(colors::HIGHLIGHT_OFF)?;
+            quote::write_name(w, &name[end..], s.quoting)
+        }
+        QuotingStyle::Shell | QuotingStyle::C => {
+            w.write_all(colors::HIGHLIGHT)?;
+            quote::write_name(w, name, s.quoting)?;
+            w.write_all(colors::HIGHLIGHT_OFF)
+        }
+    }
+}
+
+#[inline]
+fn write_plain<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
@@ -97,104 +262,78 @@ fn write_icon_plain<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
 }
 
 #[inline]
-fn write_icon_plain_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    let icon = if n.is_dir() {
-        icons::dir_icon(&n.name)
-    } else {
-        icons::file_icon(&n.name)
-    };
-    let mut buf = [0u8; 4];
-    w.write_all(icon.encode_utf8(&mut buf).as_bytes())?;
-    w.write_all(b" ")?;
-    w.write_all(n.name.as_bytes())?;
+fn write_plain_full<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    depth::write_sgr(w, q.theme.color(n), q.depth)?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
     w.write_all(colors::RESET)?;
-    if let Some((_c, s)) = n.meta.git.and_then(colors::git_marker) {
-        w.write_all(s)?;
-        w.write_all(colors::RESET)?;
-    }
     Ok(())
 }
 
 #[inline]
-fn write_icon_full<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    let icon = if n.is_dir() {
-        icons::dir_icon(&n.name)
-    } else {
-        icons::file_icon(&n.name)
-    };
+fn write_icon_plain<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
     let mut buf = [0u8; 4];
-    w.write_all(icon.encode_utf8(&mut buf).as_bytes())?;
+    w.write_all(q.theme.icon(n, &mut buf).as_bytes())?;
     w.write_all(b" ")?;
-    w.write_all(colors::color_for_name(&n.name, n.is_dir()))?;
-    w.write_all(n.name.as_bytes())?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
-    w.write_all(colors::RESET)?;
     Ok(())
 }
 
 #[inline]
-fn write_plain_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    w.write_all(n.name.as_bytes())?;
+fn write_icon_plain_git<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    let mut buf = [0u8; 4];
+    w.write_all(q.theme.icon(n, &mut buf).as_bytes())?;
+    w.write_all(b" ")?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
-    if let Some((_c, s)) = n.meta.git.and_then(colors::git_marker) {
+    if let Some(s) = n.meta.git.and_then(|g| q.theme.git_label(g)) {
         w.write_all(s)?;
-        w.write_all(colors::RESET)?;
     }
     Ok(())
 }
+
 #[inline]
-fn write_plain_gitonly<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    w.write_all(n.name.as_bytes())?;
+fn write_icon_full<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    let mut buf = [0u8; 4];
+    w.write_all(q.theme.icon(n, &mut buf).as_bytes())?;
+    w.write_all(b" ")?;
+    depth::write_sgr(w, q.theme.color(n), q.depth)?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
-    if let Some((c, s)) = n.meta.git.and_then(colors::git_marker) {
-        w.write_all(c)?;
-        w.write_all(s)?;
-        w.write_all(colors::RESET)?;
-    }
+    w.write_all(colors::RESET)?;
     Ok(())
 }
 
 #[inline]
-fn write_full_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    w.write_all(colors::color_for_name(&n.name, n.is_dir()))?;
-    w.write_all(n.name.as_bytes())?;
+fn write_plain_git<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
-    w.write_all(colors::RESET)?;
-    if let Some((c, s)) = n.meta.git.and_then(colors::git_marker) {
-        w.write_all(c)?;
+    if let Some(s) = n.meta.git.and_then(|g| q.theme.git_label(g)) {
         w.write_all(s)?;
-        w.write_all(colors::RESET)?;
     }
     Ok(())
 }
-
 #[inline]
-fn write_icon_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    let icon = if n.is_dir() {
-        icons::dir_icon(&n.name)
-    } else {
-        icons::file_icon(&n.name)
-    };
-    let mut buf = [0u8; 4];
-    w.write_all(icon.encode_utf8(&mut buf).as_bytes())?;
-    w.write_all(b" ")?;
-    w.write_all(n.name.as_bytes())?;
+fn write_full_git<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
+    depth::write_sgr(w, q.theme.color(n), q.depth)?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
-    if let Some((c, s)) = n.meta.git.and_then(colors::git_marker) {
-        w.write_all(c)?;
+    w.write_all(colors::RESET)?;
+    if let Some((c, s)) = n.meta.git.and_then(|g| q.theme.git_marker(g)) {
+        depth::write_sgr(w, c, q.depth)?;
         w.write_all(s)?;
         w.write_all(colors::RESET)?;
     }
@@ -202,23 +341,18 @@ fn write_icon_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
 }
 
 #[inline]
-fn write_icon_full_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
-    let icon = if n.is_dir() {
-        icons::dir_icon(&n.name)
-    } else {
-        icons::file_icon(&n.name)
-    };
+fn write_icon_full_git<W: Write>(w: &mut W, n: &Node, q: NameStyle<'_>) -> io::Result<()> {
     let mut buf = [0u8; 4];
-    w.write_all(icon.encode_utf8(&mut buf).as_bytes())?;
+    w.write_all(q.theme.icon(n, &mut buf).as_bytes())?;
     w.write_all(b" ")?;
-    w.write_all(colors::color_for_name(&n.name, n.is_dir()))?;
-    w.write_all(n.name.as_bytes())?;
+    depth::write_sgr(w, q.theme.color(n), q.depth)?;
+    write_label(w, n, q)?;
     if n.is_dir() {
         w.write_all(b"/")?;
     }
     w.write_all(colors::RESET)?;
-    if let Some((c, s)) = n.meta.git.and_then(colors::git_marker) {
-        w.write_all(c)?;
+    if let Some((c, s)) = n.meta.git.and_then(|g| q.theme.git_marker(g)) {
+        depth::write_sgr(w, c, q.depth)?;
         w.write_all(s)?;
         w.write_all(colors::RESET)?;
     }
@@ -228,19 +362,25 @@ fn write_icon_full_git<W: Write>(w: &mut W, n: &Node) -> io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::args::ColorMode;
+    use crate::cli::args::Charset;
     use crate::config::RenderOptions;
-    use crate::model::node::{GitState, Node};
+    use crate::model::node::{ContentMatches, Duplicates, GitState, LineCounts, MatchLine, Node};
+    use crate::renderer::guides::Guides;
 
-    fn opts(icons: bool, color: ColorMode, git: bool) -> RenderOptions {
-        RenderOptions { icons, color, git }
+    fn opts(icons: bool, color: bool, git: bool) -> RenderOptions {
+        RenderOptions {
+            icons,
+            color,
+            git,
+            ..Default::default()
+        }
     }
 
     #[test]
     fn classic_single_dir() {
         let root = Node::new_dir("root", vec![]);
         let mut buf = Vec::new();
-        let o = opts(false, ColorMode::Never, false);
+        let o = opts(false, false, false);
         render(&mut buf, &root, &o).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "root/\n");
     }
@@ -258,7 +398,7 @@ mod tests {
             ],
         );
         let mut buf = Vec::new();
-        let o = opts(false, ColorMode::Never, false);
+        let o = opts(false, false, false);
         render(&mut buf, &root, &o).unwrap();
         let got = String::from_utf8(buf).unwrap();
         let expected = "\
@@ -271,11 +411,262 @@ root/
         assert_eq!(got, expected);
     }
 
+    #[test]
+    fn charsets_indents_and_guideless_trees() {
+        let mut data = Node::new_dir("data", vec![Node::new_file("a.csv", 1)]);
+        data.meta.truncated = Some(Truncated {
+            dirs: 0,
+            files: 2,
+            size: 10,
+        });
+        let root = Node::new_dir("root", vec![data, Node::new_file("README.md", 1)]);
+        let tree = |guides| {
+            let mut buf = Vec::new();
+            let o = RenderOptions {
+                guides,
+                ..Default::default()
+            };
+            render(&mut buf, &root, &o).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!(
+            tree(Guides::new(Charset::Ascii, 4, true)),
+            "\
+root/
+|-- data/
+|   |-- a.csv
+|   `-- … and 2 more (2 files, 10 B)
+`-- README.md
+"
+        );
+        assert_eq!(
+            tree(Guides::new(Charset::Rounded, 3, true)),
+            "\
+root/
+├─ data/
+│  ├─ a.csv
+│  ╰─ … and 2 more (2 files, 10 B)
+╰─ README.md
+"
+        );
+        assert_eq!(
+            tree(Guides::new(Charset::Utf8, 2, false)),
+            "\
+root/
+  data/
+    a.csv
+    … and 2 more (2 files, 10 B)
+  README.md
+"
+        );
+    }
+
+    #[test]
+    fn guides_take_the_colors_of_their_level() {
+        let theme =
+            Theme::parse("[guides]\ncolors = [\"red\", \"green\"]\n", Depth::Ansi16).unwrap();
+        let root = Node::new_dir(
+            "root",
+            vec![Node::new_dir("src", vec![Node::new_file("main.rs", 1)])],
+        );
+        let render_with = |color| {
+            let mut buf = Vec::new();
+            let o = RenderOptions {
+                color,
+                theme: theme.clone(),
+                ..Default::default()
+            };
+            render(&mut buf, &root, &o).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let got = render_with(true);
+        assert!(got.contains("\n\u{1b}[31m└──\u{1b}[0m "), "{got:?}");
+        assert!(got.contains("\n    \u{1b}[32m└──\u{1b}[0m "), "{got:?}");
+        assert_eq!(render_with(false), "root/\n└── src/\n    └── main.rs\n");
+    }
+
+    #[test]
+    fn collapsed_dirs_show_entry_hint() {
+        let mut src = Node::new_dir("src", vec![]);
+        src.meta.collapsed = Some(42);
+        let mut docs = Node::new_dir("docs", vec![]);
+        docs.meta.collapsed = Some(1);
+        let root = Node::new_dir("root", vec![src, docs]);
+        let mut buf = Vec::new();
+        let o = opts(false, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        let expected = "\
+root/
+├── src/ (… 42 entries)
+└── docs/ (… 1 entry)
+";
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn truncated_dirs_end_with_summary_line() {
+        let mut big = Node::new_dir("data", vec![Node::new_file("0001.png", 1)]);
+        big.meta.truncated = Some(Truncated {
+            dirs: 3,
+            files: 4_210,
+            size: 1_288_490_189,
+        });
+        let root = Node::new_dir("root", vec![big, Node::new_file("README.md", 1)]);
+        let mut buf = Vec::new();
+        let o = opts(false, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        let expected = "\
+root/
+├── data/
+│   ├── 0001.png
+│   └── … and 4 213 more (3 dirs, 4 210 files, 1.2 GiB)
+└── README.md
+";
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn mount_points_show_filesystem_type() {
+        let mut nas = Node::new_dir("nas", vec![]);
+        nas.meta.mount = Some("nfs4".into());
+        nas.meta.collapsed = Some(3);
+        let root = Node::new_dir("home", vec![nas]);
+        let mut buf = Vec::new();
+        let o = opts(false, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(got, "home/\n└── nas/ [nfs4] (… 3 entries)\n");
+    }
+
+    #[test]
+    fn control_characters_are_escaped_by_default() {
+        let root = Node::new_dir("root", vec![Node::new_file("evil\n\u{1b}[2Jname", 1)]);
+        let mut buf = Vec::new();
+        let o = opts(false, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(got, "root/\n└── evil\\n\\033[2Jname\n");
+    }
+
+    #[test]
+    fn duplicate_paths_are_quoted_like_names() {
+        let mut f = Node::new_file("a.png", 4);
+        f.meta.duplicates = Some(Duplicates {
+            group: 1,
+            others: vec![b"b.png".to_vec(), b"evil\n\x1b[2J.png".to_vec()],
+            reclaimable: 8,
+        });
+        let root = Node::new_dir("root", vec![f]);
+        let mut buf = Vec::new();
+        render(&mut buf, &root, &opts(false, false, false)).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            got,
+            "root/\n└── a.png (= b.png, evil\\n\\033[2J.png; 8 B reclaimable)\n"
+        );
+    }
+
+    #[test]
+    fn quoting_style_is_applied() {
+        let root = Node::new_dir("root", vec![Node::new_file("my file", 1)]);
+        let mut buf = Vec::new();
+        let o = RenderOptions {
+            color: false,
+            quoting: QuotingStyle::Shell,
+            ..Default::default()
+        };
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(got, "root/\n└── 'my file'\n");
+    }
+
+    #[test]
+    fn loc_columns_are_aligned_on_the_root_totals() {
+        let counts = |code, comments, blanks| LineCounts {
+            code,
+            comments,
+            blanks,
+        };
+        let mut main = Node::new_file("main.rs", 1);
+        main.meta.loc = Some(counts(120, 8, 15));
+        let logo = Node::new_file("logo.png", 1);
+        let mut root = Node::new_dir("src", vec![logo, main]);
+        root.meta.loc = Some(counts(120, 8, 15));
+        let mut buf = Vec::new();
+        let o = RenderOptions {
+            color: false,
+            loc: true,
+            ..Default::default()
+        };
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            got,
+            "[120   8  15] src/\n├──               logo.png\n└── [120   8  15] main.rs\n"
+        );
+    }
+
+    #[test]
+    fn grep_matches_and_lines_are_shown() {
+        let mut main = Node::new_file("main.rs", 1);
+        main.meta.matches = Some(ContentMatches {
+            count: 2,
+            lines: vec![
+                MatchLine {
+                    number: 3,
+                    text: "// TODO: tidy".into(),
+                },
+                MatchLine {
+                    number: 9,
+                    text: "// FIXME \u{1b}[31m".into(),
+                },
+            ],
+        });
+        let mut lib = Node::new_file("lib.rs", 1);
+        lib.meta.matches = Some(ContentMatches {
+            count: 1,
+            lines: vec![],
+        });
+        let root = Node::new_dir("root", vec![Node::new_dir("src", vec![main]), lib]);
+        let mut buf = Vec::new();
+        let o = opts(false, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        let expected = "\
+root/
+├── src/
+│   └── main.rs (2 matches)
+│         3: // TODO: tidy
+│         9: // FIXME \\033[31m
+└── lib.rs (1 match)
+";
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn find_matches_are_highlighted_when_colored() {
+        let mut f = Node::new_file("app_config.rs", 1);
+        f.meta.highlight = Some((4, 10));
+        let root = Node::new_dir("root", vec![f]);
+
+        let mut buf = Vec::new();
+        render(&mut buf, &root, &opts(false, true, false)).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert!(got.contains("app_\u{1b}[7mconfig\u{1b}[27m.rs"), "{got:?}");
+
+        let mut buf = Vec::new();
+        render(&mut buf, &root, &opts(false, false, false)).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        assert_eq!(got, "root/\n└── app_config.rs\n");
+    }
+
     #[test]
     fn icons_single_dir() {
         let root = Node::new_dir("root", vec![]);
         let mut buf = Vec::new();
-        let o = opts(true, ColorMode::Never, false);
+        let o = opts(true, false, false);
         render(&mut buf, &root, &o).unwrap();
         let got = String::from_utf8(buf).unwrap();
         let expected = format!("{} root/\n", '\u{f115}');
@@ -295,7 +686,7 @@ root/
             ],
         );
         let mut buf = Vec::new();
-        let o = opts(true, ColorMode::Never, false);
+        let o = opts(true, false, false);
         render(&mut buf, &root, &o).unwrap();
         let got = String::from_utf8(buf).unwrap();
         let expected = "\
@@ -309,33 +700,58 @@ root/
     }
 
     #[test]
-    fn auto_git_only_colors_when_git_present() {
+    fn compacted_chain_uses_leaf_icon() {
+        let mut root = Node::new_dir(
+            "root",
+            vec![Node::new_dir(
+                "crates",
+                vec![Node::new_dir("src", vec![Node::new_file("lib.rs", 1)])],
+            )],
+        );
+        crate::transform::compact::compact(&mut root, false);
+        let mut buf = Vec::new();
+        let o = opts(true, false, false);
+        render(&mut buf, &root, &o).unwrap();
+        let got = String::from_utf8(buf).unwrap();
+        let expected = "\
+\u{f115} root/
+└── \u{f08de} crates/src/
+    └── \u{e7a8} lib.rs
+";
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn git_markers_follow_the_color_decision() {
         let mut f = Node::new_file("a.txt", 1);
         f.meta.git = Some(GitState::Modified);
-        let mut buf = Vec::new();
-        let o = opts(false, ColorMode::Auto, true);
-        render(&mut buf, &f, &o).unwrap();
-        let s = String::from_utf8(buf).unwrap();
-        assert!(s.starts_with("a.txt"));
-        assert!(s.contains('\u{1b}'));
+        for icons in [false, true] {
+            let mut buf = Vec::new();
+            render(&mut buf, &f, &opts(icons, false, true)).unwrap();
+            let s = String::from_utf8(buf).unwrap();
+            assert!(s.ends_with("a.txt ~\n"), "{s:?}");
+            assert!(!s.contains('\u{1b}'));
+
+            let mut buf = Vec::new();
+            render(&mut buf, &f, &opts(icons, true, true)).unwrap();
+            let s = String::from_utf8(buf).unwrap();
+            assert!(s.contains("\u{1b}[33m \u{1b}[1m~"), "{s:?}");
+        }
     }
 
     #[test]
-    fn auto_no_git_means_no_color() {
+    fn uncolored_names_carry_no_escapes() {
         let f = Node::new_file("a.txt", 1);
         let mut buf = Vec::new();
-        let o = opts(false, ColorMode::Auto, false);
-        render(&mut buf, &f, &o).unwrap();
-        let s = String::from_utf8(buf).unwrap();
-        assert_eq!(s, "a.txt\n");
-        assert!(!s.contains('\u{1b}'));
+        render(&mut buf, &f, &opts(false, false, false)).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "a.txt\n");
     }
 
     #[test]
     fn always_colors_names() {
         let f = Node::new_file("a.txt", 1);
         let mut buf = Vec::new();
-        let o = opts(false, ColorMode::Always, false);
+        let o = opts(false, true, false);
         render(&mut buf, &f, &o).unwrap();
         let s = String::from_utf8(buf).unwrap();
         assert!(s.contains('\u{1b}'));
@@ -345,7 +761,7 @@ root/
     fn always_colors_with_icons() {
         let f = Node::new_file("main.rs", 1);
         let mut buf = Vec::new();
-        let o = opts(true, ColorMode::Always, false);
+        let o = opts(true, true, false);
         render(&mut buf, &f, &o).unwrap();
         let s = String::from_utf8(buf).unwrap();
         assert!(s.contains('\u{e7a8}'));
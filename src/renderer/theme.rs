@@ -0,0 +1,635 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use globset::{Glob, GlobBuilder};
+use serde::{Deserialize, Deserializer, de};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::PathBuf,
+};
+
+use crate::{
+    model::node::{GitState, Node},
+    renderer::{
+        colors,
+        depth::Depth,
+        icons,
+        ls_colors::{self, LsColors},
+    },
+};
+
+/// Themes selectable by name with `--theme`.
+pub const BUILTIN: [(&str, &str); 4] = [
+    ("default", include_str!("themes/default.toml")),
+    ("mono", include_str!("themes/mono.toml")),
+    ("nord", include_str!("themes/nord.toml")),
+    ("gruvbox", include_str!("themes/gruvbox.toml")),
+];
+
+/// Colors, icons, Git markers and guide colors of the tree: those of the
+/// theme, then of `LS_COLORS` and `EZA_COLORS`, then the built-in ones.
+#[derive(Debug, Clone, Default)]
+pub struct Theme {
+    colors: LsColors,
+    /// Set by `inherit = false`: names the theme does not color are left
+    /// uncolored.
+    alone: bool,
+    ls_colors: LsColors,
+    icons: Icons,
+    git: Vec<(GitState, Marker)>,
+    /// Colors of the guides of each tree level, in turn.
+    guides: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Icons {
+    dir: Option<String>,
+    file: Option<String>,
+    dirs: HashMap<String, String>,
+    names: HashMap<String, String>,
+    /// Keyed by lowercase extension.
+    extensions: HashMap<String, String>,
+}
+
+/// A Git marker, colored and not.
+#[derive(Debug, Clone)]
+struct Marker {
+    color: Vec<u8>,
+    symbol: Vec<u8>,
+    plain: Vec<u8>,
+}
+
+impl Theme {
+    /// Loads the theme named or pointed to by `--theme`, or else the user
+    /// theme file when there is one, for a terminal showing `depth` colors.
+    /// `LS_COLORS` and `EZA_COLORS` are only read when `color` is set.
+    pub fn load(theme: Option<&str>, color: bool, depth: Depth) -> Result<Self, String> {
+        let builtin = theme.and_then(|t| BUILTIN.iter().find(|(name, _)| *name == t));
+        let (origin, src) = match (theme, builtin) {
+            (_, Some((name, src))) => (format!("theme {name}"), src.to_string()),
+            (Some(path), None) => {
+                let src = fs::read_to_string(path).map_err(|e| {
+                    let names: Vec<&str> = BUILTIN.iter().map(|(name, _)| *name).collect();
+                    format!(
+                        "cannot read theme {path}: {e} (built-in themes: {})",
+                        names.join(", ")
+                    )
+                })?;
+                (path.to_string(), src)
+            }
+            (None, None) => match user_file().filter(|p| p.is_file()) {
+                Some(path) => {
+                    let src = fs::read_to_string(&path)
+                        .map_err(|e| format!("cannot read theme {}: {e}", path.display()))?;
+                    (path.display().to_string(), src)
+                }
+                None => (String::new(), String::new()),
+            },
+        };
+        let mut theme = Self::parse(&src, depth).map_err(|e| format!("{origin}: {e}"))?;
+        if color {
+            theme.ls_colors = LsColors::from_env();
+        }
+        Ok(theme)
+    }
+
+    /// Reads a theme file, keeping the richest of the alternatives of each
+    /// color that `depth` shows. Unknown keys and invalid colors, symbols or
+    /// globs are errors, which name the faulty entry.
+    pub fn parse(src: &str, depth: Depth) -> Result<Self, String> {
+        let file: ThemeFile =
+            toml::from_str(src).map_err(|e| e.to_string().trim_end().to_string())?;
+        let c = file.colors;
+
+        let kinds = [
+            ("di", c.dir),
+            ("fi", c.file),
+            ("ln", c.symlink),
+            ("ex", c.executable),
+        ];
+        // Later rules win: names over globs, globs over extensions.
+        let mut rules: Vec<(Glob, Vec<u8>)> = Vec::new();
+        for (ext, style) in c.extensions {
+            let glob = GlobBuilder::new(&format!("*.{}", globset::escape(&ext)))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("colors.extensions: {e}"))?;
+            rules.push((glob, style.pick(depth)));
+        }
+        for (pattern, style) in c.globs {
+            let glob = Glob::new(&pattern).map_err(|e| format!("colors.globs: {e}"))?;
+            rules.push((glob, style.pick(depth)));
+        }
+        for (name, style) in c.names {
+            let glob =
+                Glob::new(&globset::escape(&name)).map_err(|e| format!("colors.names: {e}"))?;
+            rules.push((glob, style.pick(depth)));
+        }
+        let colors = LsColors::from_rules(
+            kinds
+                .into_iter()
+                .filter_map(|(k, s)| Some((k, s?.pick(depth)))),
+            rules,
+        );
+
+        let i = file.icons;
+        let icons = Icons {
+            dir: i.dir.map(|s| s.0),
+            file: i.file.map(|s| s.0),
+            dirs: i.dirs.into_iter().map(|(k, v)| (k, v.0)).collect(),
+            names: i.names.into_iter().map(|(k, v)| (k, v.0)).collect(),
+            extensions: i
+                .extensions
+                .into_iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v.0))
+                .collect(),
+        };
+
+        let g = file.git;
+        let git = [
+            (GitState::Modified, g.modified),
+            (GitState::Staged, g.staged),
+            (GitState::Untracked, g.untracked),
+            (GitState::Ignored, g.ignored),
+            (GitState::Renamed, g.renamed),
+            (GitState::Deleted, g.deleted),
+        ]
+        .into_iter()
+        .filter_map(|(state, m)| Some((state, Marker::new(state, m?, depth))))
+        .collect();
+
+        let guides = file
+            .guides
+            .colors
+            .into_iter()
+            .map(|s| s.pick(depth))
+            .collect();
+
+        Ok(Self {
+            colors,
+            alone: !c.inherit,
+            ls_colors: LsColors::default(),
+            icons,
+            git,
+            guides,
+        })
+    }
+
+    /// Escape sequence that starts the name of `node`, empty when nothing
+    /// colors it.
+    pub fn color(&self, node: &Node) -> &[u8] {
+        if self.alone {
+            ls_colors::layered(&[&self.colors], false, node)
+        } else {
+            let builtin = self.ls_colors.keeps_builtin();
+            ls_colors::layered(&[&self.colors, &self.ls_colors], builtin, node)
+        }
+    }
+
+    /// Icon of `node`. Names win over extensions, and the theme over the
+    /// built-in icons for the same name or extension.
+    pub fn icon<'a>(&'a self, node: &Node, buf: &'a mut [u8; 4]) -> &'a str {
+        let name = node.leaf_name();
+        let i = &self.icons;
+        let builtin = if node.is_dir() {
+            if let Some(s) = i.dirs.get(name) {
+                return s;
+            }
+            match (icons::DIR_ICONS.get(name), &i.dir) {
+                (Some(c), _) => *c,
+                (None, Some(s)) => return s,
+                (None, None) => icons::DIR_DEFAULT,
+            }
+        } else {
+            if let Some(s) = i.names.get(name) {
+                return s;
+            }
+            if let Some(c) = icons::FILE_ICONS.get(name) {
+                return c.encode_utf8(buf);
+            }
+            if let Some(s) = by_extension(&i.extensions, name) {
+                return s;
+            }
+            match (icons::ext_icon(name), &i.file) {
+                (Some(c), _) => c,
+                (None, Some(s)) => return s,
+                (None, None) => icons::FILE_DEFAULT,
+            }
+        };
+        builtin.encode_utf8(buf)
+    }
+
+    /// Color and colored symbol of the Git marker of `g`.
+    pub fn git_marker(&self, g: GitState) -> Option<(&[u8], &[u8])> {
+        match self.git.iter().find(|(s, _)| *s == g) {
+            Some((_, m)) => Some((&m.color, &m.symbol)),
+            None => colors::git_marker(g),
+        }
+    }
+
+    /// The Git marker of `g`, when colors are off.
+    pub fn git_label(&self, g: GitState) -> Option<&[u8]> {
+        match self.git.iter().find(|(s, _)| *s == g) {
+            Some((_, m)) => Some(&m.plain),
+            None => colors::git_label(g),
+        }
+    }
+
+    /// Colors of the guides of each tree level, in turn.
+    pub fn guide_colors(&self) -> &[Vec<u8>] {
+        &self.guides
+    }
+}
+
+impl Marker {
+    /// Fills what `m` leaves out with the built-in marker of `state`.
+    fn new(state: GitState, m: MarkerFile, depth: Depth) -> Self {
+        let color = match m.color {
+            Some(style) => style.pick(depth),
+            None => colors::git_marker(state)
+                .map(|(c, _)| c.to_vec())
+                .unwrap_or_default(),
+        };
+        let symbol = match m.symbol {
+            Some(s) => s.0,
+            None => colors::git_label(state)
+                .map(|l| String::from_utf8_lossy(l).trim_start().to_string())
+                .unwrap_or_default(),
+        };
+        Self {
+            color,
+            symbol: format!(" \x1b[1m{symbol}\x1b[0m").into_bytes(),
+            plain: format!(" {symbol}").into_bytes(),
+        }
+    }
+}
+
+/// Looks `name` up by its extensions, the longest first (`tar.gz` before
+/// `gz`), whatever their case.
+fn by_extension<'a>(map: &'a HashMap<String, String>, name: &str) -> Option<&'a String> {
+    if map.is_empty() {
+        return None;
+    }
+    name.match_indices('.')
+        .find_map(|(i, _)| map.get(&name[i + 1..].to_ascii_lowercase()))
+}
+
+/// `$XDG_CONFIG_HOME/arbor/theme.toml`, or `~/.config/arbor/theme.toml`.
+fn user_file() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("arbor").join("theme.toml"))
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    colors: ColorsFile,
+    icons: IconsFile,
+    git: GitFile,
+    guides: GuidesFile,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ColorsFile {
+    inherit: bool,
+    dir: Option<Style>,
+    file: Option<Style>,
+    symlink: Option<Style>,
+    executable: Option<Style>,
+    extensions: BTreeMap<String, Style>,
+    names: BTreeMap<String, Style>,
+    globs: BTreeMap<String, Style>,
+}
+
+impl Default for ColorsFile {
+    fn default() -> Self {
+        Self {
+            inherit: true,
+            dir: None,
+            file: None,
+            symlink: None,
+            executable: None,
+            extensions: BTreeMap::new(),
+            names: BTreeMap::new(),
+            globs: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct IconsFile {
+    dir: Option<Symbol>,
+    file: Option<Symbol>,
+    dirs: BTreeMap<String, Symbol>,
+    names: BTreeMap<String, Symbol>,
+    extensions: BTreeMap<String, Symbol>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct GitFile {
+    modified: Option<MarkerFile>,
+    staged: Option<MarkerFile>,
+    untracked: Option<MarkerFile>,
+    ignored: Option<MarkerFile>,
+    renamed: Option<MarkerFile>,
+    deleted: Option<MarkerFile>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct GuidesFile {
+    colors: Vec<Style>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkerFile {
+    symbol: Option<Symbol>,
+    color: Option<Style>,
+}
+
+/// Text shown in place of an icon or a Git marker. Control characters are
+/// refused, so a theme cannot write escape sequences of its own.
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct Symbol(String);
+
+impl TryFrom<String> for Symbol {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("empty symbol".to_string());
+        }
+        if s.chars().any(char::is_control) {
+            return Err(format!("symbol {s:?} holds control characters"));
+        }
+        Ok(Self(s))
+    }
+}
+
+/// Escape sequences of a theme color: `"#rrggbb"`, a 256-color index, a
+/// color name (`red`, `bright-blue`) or `none`, after optional attributes
+/// (`"bold #ff8800"`). An array lists alternatives, the richest first
+/// (`["#81a1c1", 110, "blue"]`), each with the depth it needs.
+#[derive(Debug, Clone, PartialEq)]
+struct Style(Vec<(Depth, Vec<u8>)>);
+
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+const ATTRIBUTES: [(&str, u8); 6] = [
+    ("bold", 1),
+    ("dim", 2),
+    ("italic", 3),
+    ("underline", 4),
+    ("reverse", 7),
+    ("strikethrough", 9),
+];
+
+impl Style {
+    fn parse(s: &str) -> Result<Self, String> {
+        if matches!(s.trim(), "none" | "default") {
+            return Ok(Self(vec![(Depth::Ansi16, Vec::new())]));
+        }
+        let mut codes: Vec<String> = Vec::new();
+        let mut need = Depth::Ansi16;
+        let mut color = false;
+        for word in s.split_whitespace() {
+            if let Some((_, code)) = ATTRIBUTES.iter().find(|(name, _)| *name == word) {
+                codes.push(code.to_string());
+                continue;
+            }
+            if color {
+                return Err(format!("{s:?} holds more than one color"));
+            }
+            let (code, depth) = color_code(word).ok_or_else(|| {
+                format!(
+                    "unknown color {word:?}, expected a name (red, bright-blue…), \
+                     a 256-color index, #rrggbb or none"
+                )
+            })?;
+            codes.push(code);
+            need = depth;
+            color = true;
+        }
+        if codes.is_empty() {
+            return Err("empty color".to_string());
+        }
+        let seq = format!("\x1b[{}m", codes.join(";")).into_bytes();
+        Ok(Self(vec![(need, seq)]))
+    }
+
+    /// The first alternative `depth` shows, or else the last one, which the
+    /// renderer maps to the closest color it has.
+    fn pick(self, depth: Depth) -> Vec<u8> {
+        let fits = self.0.iter().position(|(need, _)| *need <= depth);
+        let i = fits.unwrap_or(self.0.len() - 1);
+        self.0
+            .into_iter()
+            .nth(i)
+            .map(|(_, seq)| seq)
+            .unwrap_or_default()
+    }
+}
+
+/// SGR parameters of a foreground color, and the depth it needs.
+fn color_code(word: &str) -> Option<(String, Depth)> {
+    if let Some(hex) = word.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        let code = format!("38;2;{};{};{}", channel(0)?, channel(2)?, channel(4)?);
+        return Some((code, Depth::Truecolor));
+    }
+    if let Ok(index) = word.parse::<u8>() {
+        return Some((format!("38;5;{index}"), Depth::Ansi256));
+    }
+    let (base, name) = match word.strip_prefix("bright-") {
+        Some(name) => (90, name),
+        None if matches!(word, "gray" | "grey") => return Some(("90".to_string(), Depth::Ansi16)),
+        None => (30, word),
+    };
+    let i = COLOR_NAMES.iter().position(|c| *c == name)?;
+    Some(((base + i).to_string(), Depth::Ansi16))
+}
+
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Style;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color name, a 256-color index, #rrggbb or an array of them")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Style, E> {
+                Style::parse(s).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<Style, E> {
+                match u8::try_from(n) {
+                    Ok(i) => Ok(Style(vec![(
+                        Depth::Ansi256,
+                        format!("\x1b[38;5;{i}m").into_bytes(),
+                    )])),
+                    Err(_) => Err(E::custom(format!(
+                        "color index {n} is not between 0 and 255"
+                    ))),
+                }
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Style, A::Error> {
+                let mut alternatives = Vec::new();
+                while let Some(Style(alts)) = seq.next_element::<Style>()? {
+                    alternatives.extend(alts);
+                }
+                if alternatives.is_empty() {
+                    return Err(de::Error::custom("empty array of colors"));
+                }
+                Ok(Style(alternatives))
+            }
+        }
+
+        d.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::FileType;
+
+    fn parse(src: &str) -> Theme {
+        Theme::parse(src, Depth::Truecolor).unwrap()
+    }
+
+    #[test]
+    fn colors_by_kind_name_glob_and_extension() {
+        let t = parse(
+            r##"
+            [colors]
+            dir = "bold blue"
+            executable = 208
+            [colors.extensions]
+            rs = "#ff8800"
+            GZ = "red"
+            [colors.names]
+            "Cargo.toml" = "bright-yellow"
+            [colors.globs]
+            "test_*" = "none"
+            "##,
+        );
+        let color = |n: &Node| String::from_utf8(t.color(n).to_vec()).unwrap();
+        let mut exe = Node::new_file("run.rs", 1);
+        exe.meta.file_type = Some(FileType::Executable);
+
+        assert_eq!(color(&Node::new_dir("src", vec![])), "\x1b[1;34m");
+        assert_eq!(color(&exe), "\x1b[38;5;208m");
+        assert_eq!(color(&Node::new_file("main.rs", 1)), "\x1b[38;2;255;136;0m");
+        assert_eq!(color(&Node::new_file("a.tar.gz", 1)), "\x1b[31m");
+        assert_eq!(color(&Node::new_file("Cargo.toml", 1)), "\x1b[93m");
+        assert_eq!(color(&Node::new_file("test_main.rs", 1)), "");
+        // Everything else keeps the built-in colors.
+        let md = Node::new_file("a.md", 1);
+        assert_eq!(t.color(&md), colors::color_for_name("a.md", false));
+    }
+
+    #[test]
+    fn the_theme_wins_over_ls_colors_unless_it_leaves_names_out() {
+        let mut t = parse("[colors.extensions]\nrs = \"red\"\n");
+        t.ls_colors = LsColors::parse(Some("*.rs=32:*.md=35"), None);
+        assert_eq!(t.color(&Node::new_file("a.rs", 1)), b"\x1b[31m");
+        assert_eq!(t.color(&Node::new_file("a.md", 1)), b"\x1b[35m");
+
+        let mut t = parse("[colors]\ninherit = false\nfile = \"white\"\n");
+        t.ls_colors = LsColors::parse(Some("*.md=35"), None);
+        assert_eq!(t.color(&Node::new_file("a.md", 1)), b"\x1b[37m");
+        assert_eq!(t.color(&Node::new_dir("src", vec![])), b"");
+    }
+
+    #[test]
+    fn overrides_icons_and_git_markers() {
+        let t = parse(
+            r#"
+            [icons]
+            file = "-"
+            [icons.dirs]
+            src = "S"
+            [icons.extensions]
+            rs = "R"
+            [git.modified]
+            symbol = "M"
+            [git.untracked]
+            color = "bright-red"
+            "#,
+        );
+        let icon = |n: &Node| t.icon(n, &mut [0; 4]).to_string();
+        assert_eq!(icon(&Node::new_dir("src", vec![])), "S");
+        assert_eq!(icon(&Node::new_dir("docs", vec![])), "\u{f115}");
+        assert_eq!(icon(&Node::new_file("main.RS", 1)), "R");
+        assert_eq!(icon(&Node::new_file("Cargo.toml", 1)), "\u{e68b}");
+        assert_eq!(icon(&Node::new_file("LICENSE.unknown", 1)), "-");
+
+        assert_eq!(t.git_label(GitState::Modified), Some(&b" M"[..]));
+        assert_eq!(
+            t.git_marker(GitState::Modified),
+            Some((&b"\x1b[33m"[..], &b" \x1b[1mM\x1b[0m"[..]))
+        );
+        assert_eq!(
+            t.git_marker(GitState::Untracked),
+            Some((&b"\x1b[91m"[..], &b" \x1b[1m?\x1b[0m"[..]))
+        );
+        assert_eq!(
+            t.git_label(GitState::Staged),
+            colors::git_label(GitState::Staged)
+        );
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_with_their_key() {
+        let err = |src: &str| Theme::parse(src, Depth::Truecolor).unwrap_err();
+        let e = err("[colors.extensions]\nrs = \"redd\"\n");
+        assert!(e.contains("unknown color \"redd\""), "{e}");
+        assert!(e.contains("line 2"), "{e}");
+        assert!(err("[colors]\ndir = 300\n").contains("not between 0 and 255"));
+        assert!(err("[colors]\ndir = \"red blue\"\n").contains("more than one color"));
+        assert!(err("[colours]\n").contains("unknown field `colours`"));
+        assert!(err("[git.modified]\nsymbol = \"\\u001b[31m\"\n").contains("control"));
+        assert!(err("[colors.globs]\n\"[a\" = \"red\"\n").starts_with("colors.globs:"));
+    }
+
+    #[test]
+    fn picks_the_richest_alternative_the_terminal_shows() {
+        let src = "[colors]\ndir = [\"bold #81a1c1\", 110, \"bold blue\"]\nfile = \"#d8dee9\"\n";
+        let dir = Node::new_dir("src", vec![]);
+        let file = Node::new_file("a", 1);
+        let t = |depth| Theme::parse(src, depth).unwrap();
+        assert_eq!(t(Depth::Truecolor).color(&dir), b"\x1b[1;38;2;129;161;193m");
+        assert_eq!(t(Depth::Ansi256).color(&dir), b"\x1b[38;5;110m");
+        assert_eq!(t(Depth::Ansi16).color(&dir), b"\x1b[1;34m");
+        // Without an alternative, the renderer maps the color down.
+        assert_eq!(t(Depth::Ansi16).color(&file), b"\x1b[38;2;216;222;233m");
+        assert!(Theme::parse("[colors]\ndir = []\n", Depth::Ansi16).is_err());
+    }
+
+    #[test]
+    fn builtin_themes_parse() {
+        for (name, src) in BUILTIN {
+            if let Err(e) = Theme::parse(src, Depth::Ansi16) {
+                panic!("theme {name}: {e}");
+            }
+        }
+    }
+}
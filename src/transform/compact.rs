@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::model::node::Node;
+
+/// Merges chains of directories that have a single child into one node
+/// (`src/main/java/` instead of three nested lines). The root is never merged.
+///
+/// When `include_files` is false, a directory whose only child is a file is
+/// left as is, so only directory-to-directory chains are collapsed. Chains
+/// stop at directories that had files filtered out or are mount points, which
+/// would lose those marks once merged.
+pub fn compact(root: &mut Node, include_files: bool) {
+    if let Some(children) = root.children.as_mut() {
+        for c in children {
+            compact_node(c, include_files);
+        }
+    }
+}
+
+fn compact_node(node: &mut Node, include_files: bool) {
+    while node.is_dir() && node.meta.filtered.is_none() && node.meta.mount.is_none() {
+        let Some(children) = node.children.as_mut() else {
+            break;
+        };
+        if children.len() != 1 || (!children[0].is_dir() && !include_files) {
+            break;
+        }
+        let Some(child) = children.pop() else {
+            break;
+        };
+        merge(node, child);
+    }
+
+    if let Some(children) = node.children.as_mut() {
+        for c in children {
+            compact_node(c, include_files);
+        }
+    }
+}
+
+fn merge(parent: &mut Node, child: Node) {
+    let mut segments = parent
+        .segments
+        .take()
+        .unwrap_or_else(|| vec![parent.name.clone()]);
+    // `--find` ranges are relative to the child name, which now comes after
+    // the parent one and a slash.
+    let offset = parent.name_bytes().len() + 1;
+    if parent.raw_name.is_some() || child.raw_name.is_some() {
+        let mut raw = parent.os_name().to_os_string();
+        raw.push("/");
+        raw.push(child.os_name());
+        parent.raw_name = Some(raw);
+    }
+    segments.extend(child.segments.unwrap_or_else(|| vec![child.name.clone()]));
+    let highlight = child
+        .meta
+        .highlight
+        .map(|(start, end)| (start + offset, end + offset))
+        .or(parent.meta.highlight);
+
+    parent.name.push('/');
+    parent.name.push_str(&child.name);
+    parent.segments = Some(segments);
+    parent.meta = child.meta;
+    parent.meta.highlight = highlight;
+    parent.children = child.children;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::Kind;
+
+    fn java_layout() -> Node {
+        Node::new_dir(
+            "root",
+            vec![
+                Node::new_dir(
+                    "src",
+                    vec![Node::new_dir(
+                        "main",
+                        vec![Node::new_dir(
+                            "java",
+                            vec![
+                                Node::new_file("App.java", 1),
+                                Node::new_file("Util.java", 1),
+                            ],
+                        )],
+                    )],
+                ),
+                Node::new_dir("docs", vec![Node::new_file("index.md", 1)]),
+            ],
+        )
+    }
+
+    #[test]
+    fn merges_directory_chains() {
+        let mut root = java_layout();
+        compact(&mut root, false);
+
+        let src = &root.children_slice()[0];
+        assert_eq!(src.name, "src/main/java");
+        assert_eq!(
+            src.segments.as_deref(),
+            Some(&["src".to_string(), "main".to_string(), "java".to_string()][..])
+        );
+        assert_eq!(src.leaf_name(), "java");
+        assert_eq!(src.children_slice().len(), 2);
+    }
+
+    #[test]
+    fn keeps_file_chains_unless_requested() {
+        let mut root = java_layout();
+        compact(&mut root, false);
+        assert_eq!(root.children_slice()[1].name, "docs");
+
+        let mut root = java_layout();
+        compact(&mut root, true);
+        let docs = &root.children_slice()[1];
+        assert_eq!(docs.name, "docs/index.md");
+        assert_eq!(docs.meta.kind, Kind::File);
+        assert!(docs.children.is_none());
+    }
+
+    #[test]
+    fn shifts_find_highlight_onto_merged_name() {
+        let mut java = Node::new_dir("java", vec![Node::new_file("App.java", 1)]);
+        java.meta.highlight = Some((1, 3));
+        let mut root = Node::new_dir("root", vec![Node::new_dir("main", vec![java])]);
+        compact(&mut root, false);
+
+        let merged = &root.children_slice()[0];
+        assert_eq!(merged.name, "main/java");
+        assert_eq!(merged.meta.highlight, Some((6, 8)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shifts_find_highlight_past_raw_parent_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut java = Node::new_dir("java", vec![Node::new_file("App.java", 1)]);
+        java.meta.highlight = Some((1, 3));
+        let main = Node::new_dir("", vec![java]).with_os_name(OsStr::from_bytes(b"m\xffin"));
+        let mut root = Node::new_dir("root", vec![main]);
+        compact(&mut root, false);
+
+        let merged = &root.children_slice()[0];
+        assert_eq!(merged.name_bytes(), b"m\xffin/java");
+        assert_eq!(merged.meta.highlight, Some((6, 8)));
+    }
+
+    #[test]
+    fn root_is_never_merged() {
+        let mut root = Node::new_dir("root", vec![Node::new_dir("only", vec![])]);
+        compact(&mut root, true);
+        assert_eq!(root.name, "root");
+        assert_eq!(root.children_slice()[0].name, "only");
+    }
+
+    #[test]
+    fn keeps_filtered_and_mounted_directories_apart() {
+        let mut a = Node::new_dir(
+            "a",
+            vec![Node::new_dir("b", vec![Node::new_file("x.rs", 1)])],
+        );
+        a.meta.filtered = Some(2);
+        let mut mnt = Node::new_dir("mnt", vec![Node::new_dir("data", vec![])]);
+        mnt.meta.mount = Some("nfs".into());
+        let mut root = Node::new_dir("root", vec![a, mnt]);
+        compact(&mut root, true);
+
+        let names: Vec<&str> = root.children_slice().iter().map(|c| &*c.name).collect();
+        assert_eq!(names, ["a", "mnt"]);
+        assert_eq!(root.children_slice()[0].meta.filtered, Some(2));
+        assert_eq!(root.children_slice()[0].children_slice()[0].name, "b/x.rs");
+        assert_eq!(root.children_slice()[1].meta.mount.as_deref(), Some("nfs"));
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use regex::bytes::Regex;
+
+use crate::model::node::Node;
+
+#[derive(Debug, Clone)]
+pub struct FindOptions {
+    pub regex: Regex,
+    /// Number of siblings kept on each side of a match.
+    pub context: usize,
+}
+
+/// Compiles a `--find` pattern. Patterns that look like globs (`*.rs`,
+/// `test_?.py`) must match the whole name; anything else is a regex that may
+/// match anywhere in it.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    let source = if looks_like_glob(pattern) {
+        globset::Glob::new(pattern)
+            .map_err(|e| e.to_string())?
+            .regex()
+            .to_string()
+    } else {
+        pattern.to_string()
+    };
+    Regex::new(&source).map_err(|e| e.to_string())
+}
+
+fn looks_like_glob(pattern: &str) -> bool {
+    const REGEX_ONLY: &[&str] = &["\\", "(", ")", "|", "+", "^", "$", ".*", ".?"];
+    pattern.contains(['*', '?']) && !REGEX_ONLY.iter().any(|s| pattern.contains(s))
+}
+
+/// Keeps only the nodes whose name matches, their ancestors and up to
+/// `context` siblings on each side of them. Matched ranges are recorded in
+/// `meta.highlight`. Directories kept without any match below them (matched
+/// directories and context siblings) are folded into an entry count. The root
+/// is never matched nor removed.
+pub fn find(root: &mut Node, opts: &FindOptions) {
+    if let Some(children) = root.children.as_mut()
+        && !filter_children(children, opts)
+    {
+        children.clear();
+    }
+}
+
+/// Returns whether the node or one of its descendants matches. Subtrees
+/// without any match are left untouched, so the caller can still fold them.
+fn filter(node: &mut Node, opts: &FindOptions) -> bool {
+    let hit = match opts.regex.find(node.name_bytes()) {
+        Some(m) => {
+            if !m.is_empty() {
+                node.meta.highlight = Some((m.start(), m.end()));
+            }
+            true
+        }
+        None => false,
+    };
+    let below = match node.children.as_mut() {
+        Some(children) => filter_children(children, opts),
+        None => false,
+    };
+    if hit && !below {
+        fold(node);
+    }
+    hit || below
+}
+
+fn filter_children(children: &mut Vec<Node>, opts: &FindOptions) -> bool {
+    let hits: Vec<bool> = children.iter_mut().map(|c| filter(c, opts)).collect();
+    if !hits.contains(&true) {
+        return false;
+    }
+
+    let mut keep = vec![false; hits.len()];
+    for (i, _) in hits.iter().enumerate().filter(|(_, h)| **h) {
+        let lo = i.saturating_sub(opts.context);
+        let hi = (i + opts.context).min(hits.len() - 1);
+        keep[lo..=hi].fill(true);
+    }
+
+    let mut i = 0;
+    children.retain_mut(|c| {
+        let (kept, hit) = (keep[i], hits[i]);
+        i += 1;
+        if kept && !hit {
+            fold(c);
+        }
+        kept
+    });
+    true
+}
+
+fn fold(node: &mut Node) {
+    let Some(children) = node.children.as_mut() else {
+        return;
+    };
+    let hidden = children.len() + node.meta.collapsed.unwrap_or(0);
+    children.clear();
+    if hidden > 0 {
+        node.meta.collapsed = Some(hidden);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> Node {
+        Node::new_dir(
+            "ws",
+            vec![
+                Node::new_dir(
+                    "app",
+                    vec![Node::new_file("config.rs", 1), Node::new_file("main.rs", 1)],
+                ),
+                Node::new_dir("docs", vec![Node::new_file("index.md", 1)]),
+                Node::new_dir(
+                    "lib",
+                    vec![
+                        Node::new_file("a.rs", 1),
+                        Node::new_file("b.rs", 1),
+                        Node::new_dir("config", vec![Node::new_file("mod.rs", 1)]),
+                        Node::new_file("c.rs", 1),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    fn opts(pattern: &str, context: usize) -> FindOptions {
+        FindOptions {
+            regex: compile_pattern(pattern).unwrap(),
+            context,
+        }
+    }
+
+    fn names(n: &Node) -> Vec<&str> {
+        n.children_slice().iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_matches_and_their_ancestors() {
+        let mut root = workspace();
+        find(&mut root, &opts("config", 0));
+
+        assert_eq!(names(&root), ["app", "lib"]);
+        let app = &root.children_slice()[0];
+        assert_eq!(names(app), ["config.rs"]);
+        assert_eq!(app.children_slice()[0].meta.highlight, Some((0, 6)));
+
+        let config = &root.children_slice()[1].children_slice()[0];
+        assert_eq!(config.name, "config");
+        assert!(config.children_slice().is_empty());
+        assert_eq!(config.meta.collapsed, Some(1));
+    }
+
+    #[test]
+    fn context_keeps_folded_siblings() {
+        let mut root = workspace();
+        find(&mut root, &opts("^config$", 1));
+
+        assert_eq!(names(&root), ["docs", "lib"]);
+        assert_eq!(root.children_slice()[0].meta.collapsed, Some(1));
+        assert_eq!(names(&root.children_slice()[1]), ["b.rs", "config", "c.rs"]);
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        let mut root = workspace();
+        find(&mut root, &opts("*.md", 0));
+        assert_eq!(names(&root), ["docs"]);
+        let index = &root.children_slice()[0].children_slice()[0];
+        assert_eq!(index.meta.highlight, Some((0, 8)));
+
+        let mut root = workspace();
+        find(&mut root, &opts("?.rs", 0));
+        assert_eq!(names(&root), ["lib"]);
+        assert_eq!(names(&root.children_slice()[0]), ["a.rs", "b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn no_match_leaves_an_empty_root() {
+        let mut root = workspace();
+        find(&mut root, &opts("nothing", 2));
+        assert_eq!(root.name, "ws");
+        assert!(root.children_slice().is_empty());
+    }
+
+    #[test]
+    fn detects_globs() {
+        assert!(looks_like_glob("*.rs"));
+        assert!(looks_like_glob("test_?.py"));
+        assert!(!looks_like_glob("config"));
+        assert!(!looks_like_glob("^main\\.rs$"));
+        assert!(!looks_like_glob("foo.*bar"));
+    }
+}
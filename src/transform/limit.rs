@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::model::node::{Node, Truncated};
+
+/// Keeps at most `max` children per directory, in their current order, and
+/// records what was dropped so renderers can print an overflow summary.
+pub fn limit_entries(node: &mut Node, max: usize) {
+    let Some(children) = node.children.as_mut() else {
+        return;
+    };
+
+    if children.len() > max {
+        let mut summary = Truncated::default();
+        for dropped in children.drain(max..) {
+            if dropped.is_dir() {
+                summary.dirs += 1;
+            } else {
+                summary.files += 1;
+            }
+            summary.size += dropped.total_size();
+        }
+        node.meta.truncated = Some(summary);
+    }
+
+    for c in children {
+        limit_entries(c, max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_overflow_and_summarizes_it() {
+        let mut root = Node::new_dir(
+            "root",
+            vec![
+                Node::new_file("a", 1),
+                Node::new_file("b", 2),
+                Node::new_dir("c", vec![Node::new_file("x", 40)]),
+                Node::new_file("d", 8),
+            ],
+        );
+        limit_entries(&mut root, 2);
+
+        assert_eq!(root.children_slice().len(), 2);
+        assert_eq!(
+            root.meta.truncated,
+            Some(Truncated {
+                dirs: 1,
+                files: 1,
+                size: 48,
+            })
+        );
+    }
+
+    #[test]
+    fn small_dirs_are_untouched() {
+        let mut root = Node::new_dir("root", vec![Node::new_file("a", 1)]);
+        limit_entries(&mut root, 1);
+        assert!(root.meta.truncated.is_none());
+        assert_eq!(root.children_slice().len(), 1);
+    }
+}
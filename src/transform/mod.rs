@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+pub mod compact;
+pub mod find;
+pub mod limit;
+pub mod sort;
+
+use crate::{cli::args::SortKey, config::TransformOptions, model::node::Node};
+
+/// Applies the configured `Node` transformations, in order, before rendering.
+pub fn apply(root: &mut Node, opts: &TransformOptions) {
+    if let Some(f) = &opts.find {
+        find::find(root, f);
+    }
+    if opts.compact {
+        compact::compact(root, !opts.compact_dirs_only);
+    }
+    if opts.sort != SortKey::Name {
+        sort::sort(root, opts.sort);
+    }
+    if let Some(max) = opts.max_entries {
+        limit::limit_entries(root, max);
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::{cli::args::SortKey, model::node::Node};
+use std::cmp::Ordering;
+
+/// Reorders the children of every directory. The walker already yields
+/// entries by name, so `SortKey::Name` only needs to run on hand-built trees.
+pub fn sort(root: &mut Node, key: SortKey) {
+    match key {
+        SortKey::Name => sort_with(root, &|a, b| a.name.cmp(&b.name)),
+        SortKey::Kind => sort_with(root, &|a, b| {
+            b.is_dir()
+                .cmp(&a.is_dir())
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortKey::Size => {
+            sort_by_size(root);
+        }
+    }
+}
+
+fn sort_with(node: &mut Node, cmp: &dyn Fn(&Node, &Node) -> Ordering) {
+    if let Some(children) = node.children.as_mut() {
+        children.sort_by(|a, b| cmp(a, b));
+        for c in children {
+            sort_with(c, cmp);
+        }
+    }
+}
+
+/// Largest first, computing every directory size once on the way up.
+fn sort_by_size(node: &mut Node) -> u64 {
+    let Some(children) = node.children.as_mut() else {
+        return node.meta.size.unwrap_or(0);
+    };
+
+    let mut sized: Vec<(u64, Node)> = children
+        .drain(..)
+        .map(|mut c| (sort_by_size(&mut c), c))
+        .collect();
+    sized.sort_by(|(sa, a), (sb, b)| sb.cmp(sa).then_with(|| a.name.cmp(&b.name)));
+
+    let total = sized.iter().map(|(s, _)| s).sum();
+    children.extend(sized.into_iter().map(|(_, c)| c));
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: &Node) -> Vec<&str> {
+        n.children_slice().iter().map(|c| c.name.as_str()).collect()
+    }
+
+    fn tree() -> Node {
+        Node::new_dir(
+            "root",
+            vec![
+                Node::new_file("a.txt", 10),
+                Node::new_dir("big", vec![Node::new_file("blob", 500)]),
+                Node::new_file("c.txt", 100),
+                Node::new_dir("empty", vec![]),
+            ],
+        )
+    }
+
+    #[test]
+    fn size_puts_largest_first_including_dirs() {
+        let mut root = tree();
+        sort(&mut root, SortKey::Size);
+        assert_eq!(names(&root), ["big", "c.txt", "a.txt", "empty"]);
+    }
+
+    #[test]
+    fn kind_puts_dirs_first() {
+        let mut root = tree();
+        sort(&mut root, SortKey::Kind);
+        assert_eq!(names(&root), ["big", "empty", "a.txt", "c.txt"]);
+    }
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use crate::{
+    helpers,
+    model::node::{Node, VerifyState},
+    renderer::manifest::{path_prefix, push_name},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Digests listed in a checksum manifest, by path bytes.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    entries: HashMap<Vec<u8>, String>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Parses `sha256sum` / `b3sum` output: `<hex>  <path>` lines, with `*`
+    /// instead of the second space for binary mode, and a leading backslash
+    /// when the path is escaped.
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let mut entries = HashMap::new();
+        for (i, line) in data.split(|&b| b == b'\n').enumerate() {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+            let (hex, path) = parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: not a checksum line", i + 1),
+                )
+            })?;
+            entries.insert(path, hex);
+        }
+        Ok(Self { entries })
+    }
+}
+
+fn parse_line(line: &[u8]) -> Option<(String, Vec<u8>)> {
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let sep = line.iter().position(|&b| b == b' ')?;
+    let (hex, rest) = line.split_at(sep);
+    if hex.is_empty() || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let path = match rest {
+        [b' ', b' ' | b'*', path @ ..] if !path.is_empty() => path,
+        _ => return None,
+    };
+    let path = if escaped {
+        unescape(path)?
+    } else {
+        path.to_vec()
+    };
+    Some((String::from_utf8_lossy(hex).to_ascii_lowercase(), path))
+}
+
+fn unescape(path: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(path.len());
+    let mut it = path.iter();
+    while let Some(&b) = it.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        out.push(match it.next()? {
+            b'\\' => b'\\',
+            b'n' => b'\n',
+            b'r' => b'\r',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+/// Number of files in each `--verify` state, over every root.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub ok: usize,
+    pub changed: usize,
+    pub missing: usize,
+    pub new: usize,
+}
+
+impl Tally {
+    pub fn is_clean(&self) -> bool {
+        self.changed == 0 && self.missing == 0 && self.new == 0
+    }
+
+    fn count(&mut self, state: VerifyState) {
+        match state {
+            VerifyState::Ok => self.ok += 1,
+            VerifyState::Changed => self.changed += 1,
+            VerifyState::Missing => self.missing += 1,
+            VerifyState::New => self.new += 1,
+        }
+    }
+}
+
+pub fn write_tally<W: Write>(mut w: W, t: &Tally) -> io::Result<()> {
+    writeln!(
+        w,
+        "{} ok, {} changed, {} missing, {} new",
+        t.ok, t.changed, t.missing, t.new
+    )
+}
+
+impl Manifest {
+    /// Marks the files of `node`, the tree walked from `root`, as ok, changed
+    /// or new, and adds the files listed under `root` that are gone from disk
+    /// as missing. Listed files the walk did not reach, below `--depth` or
+    /// filtered out, are left unchecked. Files without a digest (unreadable)
+    /// count as changed.
+    pub fn verify(&self, node: &mut Node, root: &Path, tally: &mut Tally) {
+        let mut buf = path_prefix(root);
+        let mut seen = HashSet::new();
+        for c in node.children.iter_mut().flatten() {
+            self.mark(c, &mut buf, &mut seen, tally);
+        }
+
+        let prefix = path_prefix(root);
+        let mut missing: Vec<&[u8]> = self
+            .entries
+            .keys()
+            .filter(|p| !seen.contains(p.as_slice()))
+            .filter(|p| fs::symlink_metadata(helpers::os_string(p)).is_err())
+            .filter_map(|p| strip_root(p, &prefix))
+            .collect();
+        missing.sort_unstable();
+        for rel in missing {
+            node.insert_path(rel, false, |n| n.meta.verify = Some(VerifyState::Missing));
+            tally.count(VerifyState::Missing);
+        }
+    }
+
+    fn mark(
+        &self,
+        node: &mut Node,
+        buf: &mut Vec<u8>,
+        seen: &mut HashSet<Vec<u8>>,
+        tally: &mut Tally,
+    ) {
+        let keep = buf.len();
+        push_name(buf, node.name_bytes());
+        if !node.is_dir() {
+            let state = match self.entries.get(buf.as_slice()) {
+                None => VerifyState::New,
+                Some(hex) if node.meta.digest.as_ref().is_some_and(|d| &d.hex == hex) => {
+                    VerifyState::Ok
+                }
+                Some(_) => VerifyState::Changed,
+            };
+            seen.insert(buf.clone());
+            node.meta.verify = Some(state);
+            tally.count(state);
+        }
+        for c in node.children.iter_mut().flatten() {
+            self.mark(c, buf, seen, tally);
+        }
+        buf.truncate(keep);
+    }
+}
+
+/// Path of a manifest entry relative to the root it is listed under, if any.
+fn strip_root<'a>(path: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(prefix)?;
+    if prefix.ends_with(b"/") {
+        Some(rest)
+    } else {
+        rest.strip_prefix(b"/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::node::Digest;
+
+    fn hashed(name: &str, hex: &str) -> Node {
+        let mut n = Node::new_file(name, 1);
+        n.meta.digest = Some(Digest {
+            algo: "sha256",
+            hex: hex.to_string(),
+        });
+        n
+    }
+
+    #[test]
+    fn parses_text_binary_and_escaped_lines() {
+        let m = Manifest::parse(b"AA  a.txt\nbb *bin/b.dat\r\n\\cc  x\\\\y\\nz\n\n").unwrap();
+        assert_eq!(m.entries[&b"a.txt"[..]], "aa");
+        assert_eq!(m.entries[&b"bin/b.dat"[..]], "bb");
+        assert_eq!(m.entries[&b"x\\y\nz"[..]], "cc");
+        assert!(Manifest::parse(b"not a manifest\n").is_err());
+    }
+
+    #[test]
+    fn marks_files_and_adds_missing_ones() {
+        let m = Manifest::parse(b"aa  src/main.rs\nbb  src/lib.rs\ncc  gone/old.rs\n").unwrap();
+        let mut root = Node::new_dir(
+            ".",
+            vec![
+                hashed("new.rs", "dd"),
+                Node::new_dir("src", vec![hashed("lib.rs", "ff"), hashed("main.rs", "aa")]),
+            ],
+        );
+        let mut tally = Tally::default();
+        m.verify(&mut root, Path::new("."), &mut tally);
+
+        assert_eq!(
+            tally,
+            Tally {
+                ok: 1,
+                changed: 1,
+                missing: 1,
+                new: 1
+            }
+        );
+        let src = &root.children_slice()[2];
+        assert_eq!(
+            src.children_slice()[0].meta.verify,
+            Some(VerifyState::Changed)
+        );
+        assert_eq!(src.children_slice()[1].meta.verify, Some(VerifyState::Ok));
+        assert_eq!(root.children_slice()[1].meta.verify, Some(VerifyState::New));
+        let gone = &root.children_slice()[0];
+        assert_eq!(gone.name, "gone");
+        assert_eq!(gone.meta.verify, Some(VerifyState::Missing));
+        assert_eq!(gone.children_slice()[0].name, "old.rs");
+        assert_eq!(
+            gone.children_slice()[0].meta.verify,
+            Some(VerifyState::Missing)
+        );
+    }
+
+    #[test]
+    fn only_entries_under_the_root_go_missing() {
+        let m = Manifest::parse(b"aa  dist/app.js\nbb  other/x\n").unwrap();
+        let mut root = Node::new_dir("dist", vec![]);
+        let mut tally = Tally::default();
+        m.verify(&mut root, Path::new("dist"), &mut tally);
+        assert_eq!(tally.missing, 1);
+        assert_eq!(root.children_slice()[0].name, "app.js");
+    }
+}
@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright (c) 2025 Benjamin Grolleau and Angelo Tunney
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    path::Path,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+
+use crate::{
+    config::{AppConfig, WalkOptions},
+    fs_scan::walk,
+    git::{GitCache, write_git_branch},
+    helpers,
+    model::node::{Change, Node},
+    renderer::{manifest::push_name, stdout},
+    transform,
+};
+
+/// Events closer than this are handled together.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// How long changed entries stay highlighted.
+const HIGHLIGHT_FOR: Duration = Duration::from_millis(1500);
+/// Moves the cursor home and clears the screen.
+const CLEAR: &[u8] = b"\x1b[H\x1b[2J";
+
+/// An entry that changed since the previous frame, by path below the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Seen {
+    path: Vec<u8>,
+    change: Change,
+    is_dir: bool,
+}
+
+/// Draws the tree of `root`, then redraws it whenever files below it change,
+/// until the watcher stops. Only the directories holding changed entries are
+/// walked again; changes in directories that are not shown (ignored, hidden,
+/// below `--depth`) are dropped.
+pub fn run<W: Write>(out: &mut W, root: &Path, config: &AppConfig) -> io::Result<()> {
+    let opts = config.walk_for(0);
+    let mut tree = walk::walk_path(root, &opts)?;
+    let abs = root.canonicalize()?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(io::Error::other)?;
+    watcher
+        .watch(&abs, RecursiveMode::Recursive)
+        .map_err(io::Error::other)?;
+
+    let mut changes: Vec<Seen> = Vec::new();
+    draw(out, root, &tree, &changes, config)?;
+    loop {
+        let first = if changes.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(HIGHLIGHT_FOR)
+        };
+        let mut events = match first {
+            Ok(ev) => vec![ev],
+            Err(RecvTimeoutError::Timeout) => {
+                changes.clear();
+                draw(out, root, &tree, &changes, config)?;
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        while let Ok(ev) = rx.recv_timeout(DEBOUNCE) {
+            events.push(ev);
+        }
+
+        let touched = changed_paths(events, &abs);
+        let dirs = affected_dirs(&tree, root, &touched, &opts);
+        let mut found = Vec::new();
+        let mut redraw = false;
+        for dir in dirs {
+            redraw |= rewalk(&mut tree, root, &dir, &touched, &opts, &mut found);
+        }
+        if redraw {
+            changes = found;
+            draw(out, root, &tree, &changes, config)?;
+        }
+    }
+}
+
+fn draw<W: Write>(
+    out: &mut W,
+    root: &Path,
+    tree: &Node,
+    changes: &[Seen],
+    config: &AppConfig,
+) -> io::Result<()> {
+    let mut frame = tree.clone();
+    for seen in changes {
+        let node = match seen.change {
+            Change::Removed => frame.insert_path(&seen.path, seen.is_dir, |_| {}),
+            Change::Added | Change::Modified => match frame.find_mut(&seen.path) {
+                Some(node) => node,
+                None => continue,
+            },
+        };
+        node.meta.change = Some(seen.change);
+        node.meta.highlight = Some((0, node.name_bytes().len()));
+    }
+    if config.git.enabled {
+        GitCache::default().enrich(&mut frame, root);
+    }
+    transform::apply(&mut frame, &config.transform);
+
+    out.write_all(CLEAR)?;
+    if config.git.show_branch {
+        write_git_branch(out, root);
+    }
+    stdout::render(&mut *out, &frame, &config.render)?;
+    out.flush()
+}
+
+/// Paths below the root touched by file events. Reads are left out, as the
+/// walk itself reads files.
+fn changed_paths(events: Vec<notify::Result<Event>>, abs_root: &Path) -> HashSet<Vec<u8>> {
+    events
+        .into_iter()
+        .flatten()
+        .filter(|ev| !matches!(ev.kind, EventKind::Access(_)))
+        .flat_map(|ev| ev.paths)
+        .filter_map(|p| {
+            p.strip_prefix(abs_root)
+                .ok()
+                .map(|rel| rel.as_os_str().as_encoded_bytes().to_vec())
+        })
+        .filter(|rel| !rel.is_empty())
+        .collect()
+}
+
+/// Directories to walk again: the shown parents of the touched paths, without
+/// those inside another one. File filters prune the directories without a
+/// match, so with them a touched path the walk reaches is walked again from
+/// its nearest shown ancestor. `--focus` and `--min-depth` shape the whole
+/// tree, and `--duplicates` compares files across it, so with them the root
+/// is walked again instead.
+fn affected_dirs(
+    tree: &Node,
+    root: &Path,
+    touched: &HashSet<Vec<u8>>,
+    opts: &WalkOptions,
+) -> Vec<Vec<u8>> {
+    let mut dirs: Vec<Vec<u8>> = touched
+        .iter()
+        .filter_map(|p| {
+            let dir = parent(p);
+            if is_expanded(tree, dir, opts.depth) {
+                return Some(dir.to_vec());
+            }
+            let pruned = opts.has_file_filters()
+                && opts.depth.is_none_or(|d| level(dir) < d)
+                && walk::reaches(root, Path::new(&helpers::os_string(p)), opts);
+            pruned.then(|| shown_ancestor(tree, dir).to_vec())
+        })
+        .collect();
+    if dirs.is_empty() {
+        return dirs;
+    }
+    if !opts.focus.is_empty() || opts.min_depth.is_some() || opts.duplicates {
+        return vec![Vec::new()];
+    }
+    dirs.sort_unstable();
+    dirs.dedup();
+    let mut kept: Vec<Vec<u8>> = Vec::new();
+    for dir in dirs {
+        if !kept.iter().any(|k| is_within(&dir, k)) {
+            kept.push(dir);
+        }
+    }
+    kept
+}
+
+/// Whether `dir` is shown with its entries.
+fn is_expanded(tree: &Node, dir: &[u8], depth: Option<usize>) -> bool {
+    depth.is_none_or(|d| level(dir) < d) && tree.find(dir).is_some_and(Node::is_dir)
+}
+
+/// The nearest ancestor of `dir`, or `dir` itself, that is a shown directory.
+fn shown_ancestor<'a>(tree: &Node, mut dir: &'a [u8]) -> &'a [u8] {
+    while !dir.is_empty() && !tree.find(dir).is_some_and(Node::is_dir) {
+        dir = parent(dir);
+    }
+    dir
+}
+
+fn parent(path: &[u8]) -> &[u8] {
+    match path.iter().rposition(|&b| b == b'/') {
+        Some(i) => &path[..i],
+        None => b"",
+    }
+}
+
+fn level(dir: &[u8]) -> usize {
+    dir.split(|&b| b == b'/').filter(|p| !p.is_empty()).count()
+}
+
+fn is_within(path: &[u8], dir: &[u8]) -> bool {
+    dir.is_empty() || (path.starts_with(dir) && path.get(dir.len()) == Some(&b'/'))
+}
+
+/// Walks `dir` again and puts the result in place in `tree`. Returns whether
+/// the subtree changed; the entries that did are added to `found`.
+fn rewalk(
+    tree: &mut Node,
+    root: &Path,
+    dir: &[u8],
+    touched: &HashSet<Vec<u8>>,
+    opts: &WalkOptions,
+    found: &mut Vec<Seen>,
+) -> bool {
+    let mut sub = opts.clone();
+    sub.depth = opts.depth.map(|d| d - level(dir));
+    let Ok(mut fresh) = walk::walk_path(&root.join(helpers::os_string(dir)), &sub) else {
+        return false;
+    };
+    let Some(node) = tree.find_mut(dir) else {
+        return false;
+    };
+    fresh.name = node.name.clone();
+    fresh.raw_name = node.raw_name.clone();
+    if *node == fresh {
+        return false;
+    }
+    let old = std::mem::replace(node, fresh);
+    diff(&old, node, dir, touched, found);
+    true
+}
+
+/// Records the entries added, removed or modified between two walks of the
+/// same directory. Files are modified when their size changed or they were
+/// written to. Only the top of a removed subtree is recorded.
+fn diff(old: &Node, new: &Node, dir: &[u8], touched: &HashSet<Vec<u8>>, found: &mut Vec<Seen>) {
+    let (mut before, mut after) = (HashMap::new(), HashMap::new());
+    index(old, &mut dir.to_vec(), &mut before);
+    index(new, &mut dir.to_vec(), &mut after);
+
+    let mut seen: Vec<Seen> = Vec::new();
+    for (path, &(is_dir, size)) in &after {
+        let change = match before.get(path) {
+            None => Change::Added,
+            Some(&(_, old_size)) if !is_dir && (old_size != size || touched.contains(path)) => {
+                Change::Modified
+            }
+            Some(_) => continue,
+        };
+        seen.push(Seen {
+            path: path.clone(),
+            change,
+            is_dir,
+        });
+    }
+    let mut removed: Vec<(&Vec<u8>, bool)> = before
+        .iter()
+        .filter(|(p, _)| !after.contains_key(*p))
+        .map(|(p, &(is_dir, _))| (p, is_dir))
+        .collect();
+    removed.sort_unstable();
+    let mut tops: Vec<&[u8]> = Vec::new();
+    for (path, is_dir) in removed {
+        if tops.iter().any(|t| is_within(path, t)) {
+            continue;
+        }
+        tops.push(path);
+        seen.push(Seen {
+            path: path.clone(),
+            change: Change::Removed,
+            is_dir,
+        });
+    }
+    seen.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+    found.extend(seen);
+}
+
+/// Kind and size of every entry below `node`, by path.
+fn index(node: &Node, buf: &mut Vec<u8>, out: &mut HashMap<Vec<u8>, (bool, Option<u64>)>) {
+    for c in node.children_slice() {
+        let keep = buf.len();
+        push_name(buf, c.name_bytes());
+        out.insert(buf.clone(), (c.is_dir(), c.meta.size));
+        index(c, buf, out);
+        buf.truncate(keep);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_scan::types::TypeFilter;
+
+    fn tree() -> Node {
+        Node::new_dir(
+            "ws",
+            vec![
+                Node::new_dir(
+                    "src",
+                    vec![
+                        Node::new_dir("cli", vec![Node::new_file("args.rs", 10)]),
+                        Node::new_file("main.rs", 5),
+                    ],
+                ),
+                Node::new_file("README.md", 3),
+            ],
+        )
+    }
+
+    fn set(paths: &[&str]) -> HashSet<Vec<u8>> {
+        paths.iter().map(|p| p.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn only_shown_directories_are_walked_again() {
+        let opts = WalkOptions::default();
+        let touched = set(&[
+            "src/cli/args.rs",
+            "src/main.rs",
+            "target/debug/app",
+            ".git/index",
+        ]);
+        assert_eq!(
+            affected_dirs(&tree(), Path::new("ws"), &touched, &opts),
+            [b"src".to_vec()]
+        );
+
+        let touched = set(&["README.md", "src/main.rs"]);
+        assert_eq!(
+            affected_dirs(&tree(), Path::new("ws"), &touched, &opts),
+            [Vec::<u8>::new()]
+        );
+
+        let opts = WalkOptions {
+            depth: Some(1),
+            ..Default::default()
+        };
+        assert!(affected_dirs(&tree(), Path::new("ws"), &set(&["src/main.rs"]), &opts).is_empty());
+
+        let opts = WalkOptions {
+            duplicates: true,
+            ..Default::default()
+        };
+        let touched = set(&["src/cli/args.rs"]);
+        assert_eq!(
+            affected_dirs(&tree(), Path::new("ws"), &touched, &opts),
+            [Vec::<u8>::new()]
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for f in ["src/a.rs", "docs/x.md", ".cache/c.rs"] {
+            let path = root.join(f);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let opts = WalkOptions {
+            types: TypeFilter {
+                select: vec!["rust".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pruned = walk::walk_path(root, &opts).unwrap();
+        assert!(pruned.find(b"docs").is_none());
+        std::fs::write(root.join("docs/b.rs"), "").unwrap();
+        let touched = set(&["docs/b.rs"]);
+        assert_eq!(
+            affected_dirs(&pruned, root, &touched, &opts),
+            [Vec::<u8>::new()]
+        );
+        let touched = set(&[".cache/c.rs", "docs/gone.rs"]);
+        assert!(affected_dirs(&pruned, root, &touched, &opts).is_empty());
+    }
+
+    #[test]
+    fn diff_finds_added_modified_and_removed_entries() {
+        let old = tree();
+        let new = Node::new_dir(
+            "ws",
+            vec![
+                Node::new_dir("src", vec![Node::new_file("main.rs", 6)]),
+                Node::new_file("README.md", 3),
+                Node::new_file("TODO", 1),
+            ],
+        );
+        let mut found = Vec::new();
+        diff(&old, &new, b"", &set(&["README.md"]), &mut found);
+
+        let got: Vec<(&[u8], Change)> = found.iter().map(|s| (&s.path[..], s.change)).collect();
+        assert_eq!(
+            got,
+            [
+                (&b"README.md"[..], Change::Modified),
+                (b"TODO", Change::Added),
+                (b"src/cli", Change::Removed),
+                (b"src/main.rs", Change::Modified),
+            ]
+        );
+    }
+}
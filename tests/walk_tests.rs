@@ -2,9 +2,15 @@ use std::fs::{self, File};
 use std::path::Path;
 use tempfile::TempDir;
 
-use arbor::config::WalkOptions;
-use arbor::fs_scan::walk::walk_path;
-use arbor::model::node::Kind;
+use arbor::cli::args::{Args, HashAlgo};
+use arbor::config::{AppConfig, WalkOptions};
+use arbor::diff;
+use arbor::fs_scan::cache::ScanCache;
+use arbor::fs_scan::snapshot;
+use arbor::fs_scan::walk::{walk_path, walk_path_cached};
+use arbor::model::node::{Change, FileType, Kind};
+use arbor::renderer::json;
+use clap::Parser;
 
 fn make_fs_tree() -> (TempDir, std::path::PathBuf) {
     let tmp = TempDir::new().expect("tmpdir");
@@ -54,6 +60,7 @@ fn default_hides_dotfiles_and_gitignored() {
         include_hidden: false,
         follow_gitignore: true,
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(Path::new(&root), &opts).expect("walk");
@@ -74,6 +81,7 @@ fn hidden_files_shows_dot_git_but_does_not_descend() {
         include_hidden: true,
         follow_gitignore: true,
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(Path::new(&root), &opts).expect("walk");
@@ -100,6 +108,7 @@ fn show_gitignored_entries_when_gitignore_flag_is_on() {
         include_hidden: false,
         follow_gitignore: false,
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(Path::new(&root), &opts).expect("walk");
@@ -119,6 +128,7 @@ fn hidden_plus_gitignore_flag_shows_both_dot_git_and_target() {
         include_hidden: true,    // show dotfiles
         follow_gitignore: false, // show gitignored
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(Path::new(&root), &opts).expect("walk");
@@ -139,6 +149,7 @@ fn deep_structure_is_preserved() {
         include_hidden: false,
         follow_gitignore: true,
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(Path::new(&root), &opts).expect("walk");
@@ -168,6 +179,7 @@ fn walk_with_no_depth_includes_all() {
         include_hidden: false,
         follow_gitignore: true,
         depth: None,
+        ..Default::default()
     };
 
     let tree = walk_path(root, &opts).unwrap();
@@ -219,6 +231,7 @@ fn walk_with_depth_limits_traversal() {
         include_hidden: false,
         follow_gitignore: true,
         depth: Some(1),
+        ..Default::default()
     };
 
     let tree = walk_path(root, &opts).unwrap();
@@ -247,3 +260,587 @@ fn walk_with_depth_limits_traversal() {
         "level2 should be skipped due to depth limit"
     );
 }
+
+#[test]
+fn walk_with_depth_hint_counts_cut_off_entries() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+
+    let level1 = root.join("level1");
+    fs::create_dir_all(level1.join("level2")).unwrap();
+    File::create(level1.join("a.txt")).unwrap();
+    File::create(level1.join("b.txt")).unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        depth: Some(1),
+        depth_hint: true,
+        ..Default::default()
+    };
+
+    let tree = walk_path(root, &opts).unwrap();
+    let level1_node = find_child(&tree, "level1").expect("level1");
+
+    assert!(level1_node.children.as_deref().unwrap_or(&[]).is_empty());
+    assert_eq!(level1_node.meta.collapsed, Some(3));
+    assert_eq!(tree.meta.collapsed, None);
+}
+
+#[test]
+fn walk_without_depth_has_no_hidden_cap() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+
+    let mut deep = root.to_path_buf();
+    for i in 0..120 {
+        deep.push(format!("d{i}"));
+    }
+    fs::create_dir_all(&deep).unwrap();
+    File::create(deep.join("leaf.txt")).unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        ..Default::default()
+    };
+
+    let mut node = &walk_path(root, &opts).unwrap();
+    for i in 0..120 {
+        node = find_child(node, &format!("d{i}")).expect("nested dir");
+    }
+    assert!(find_child(node, "leaf.txt").is_some());
+}
+
+#[test]
+fn walk_with_min_depth_keeps_only_deep_entries_and_their_ancestors() {
+    let (_tmp, root) = make_fs_tree();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        min_depth: Some(3),
+        ..Default::default()
+    };
+
+    let tree = walk_path(Path::new(&root), &opts).expect("walk");
+    let top = list_top_level(&tree);
+
+    // src/mod.rs is at depth 2, so src/ has nothing left to show
+    assert_eq!(top, vec!["dir".to_string()]);
+    let sub = find_child(find_child(&tree, "dir").unwrap(), "sub").expect("sub");
+    assert_eq!(list_top_level(sub), vec!["file.txt".to_string()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_names_keep_their_bytes() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    File::create(root.join(OsStr::from_bytes(b"caf\xe9.txt"))).unwrap();
+    File::create(root.join("line\nbreak")).unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+    let kids = tree.children.as_deref().unwrap();
+
+    let cafe = kids
+        .iter()
+        .find(|n| n.raw_name.is_some())
+        .expect("non UTF-8 name");
+    assert_eq!(cafe.name_bytes(), b"caf\xe9.txt");
+    assert_eq!(cafe.name, "caf\u{fffd}.txt");
+
+    let newline = find_child(&tree, "line\nbreak").expect("newline name");
+    assert!(newline.raw_name.is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn executables_get_their_type_and_links_are_skipped() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::write(root.join("run.sh"), b"#!/bin/sh\n").unwrap();
+    fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(root.join("a.txt"), b"a").unwrap();
+    symlink("a.txt", root.join("link")).unwrap();
+    symlink("missing", root.join("orphan")).unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+    let ft = |name| find_child(&tree, name).expect(name).meta.file_type;
+    assert_eq!(ft("a.txt"), None);
+    assert_eq!(ft("run.sh"), Some(FileType::Executable));
+    assert_eq!(list_top_level(&tree), ["a.txt", "run.sh"]);
+}
+
+#[test]
+fn size_filters_prune_directories_without_matches() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("small")).unwrap();
+    fs::create_dir_all(root.join("mixed")).unwrap();
+    fs::write(root.join("small/a.txt"), b"tiny").unwrap();
+    fs::write(root.join("mixed/b.txt"), b"tiny").unwrap();
+    fs::write(root.join("mixed/big.bin"), vec![0u8; 4096]).unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        min_size: Some(1024),
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+
+    assert_eq!(list_top_level(&tree), vec!["mixed".to_string()]);
+    let mixed = find_child(&tree, "mixed").unwrap();
+    assert_eq!(list_top_level(mixed), vec!["big.bin".to_string()]);
+    assert_eq!(mixed.meta.filtered, Some(1));
+    // small/ was pruned, its filtered file is reported on the root
+    assert_eq!(tree.meta.filtered, Some(1));
+}
+
+#[test]
+fn mtime_filters_keep_recent_files() {
+    use std::time::{Duration, SystemTime};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    let old = File::create(root.join("old.log")).unwrap();
+    old.set_modified(SystemTime::now() - Duration::from_secs(7 * 86_400))
+        .unwrap();
+    File::create(root.join("new.log")).unwrap();
+
+    let hour_ago = SystemTime::now() - Duration::from_secs(3_600);
+
+    let newer = WalkOptions {
+        follow_gitignore: true,
+        newer_than: Some(hour_ago),
+        ..Default::default()
+    };
+    let tree = walk_path(root, &newer).unwrap();
+    assert_eq!(list_top_level(&tree), vec!["new.log".to_string()]);
+
+    let older = WalkOptions {
+        follow_gitignore: true,
+        older_than: Some(hour_ago),
+        ..Default::default()
+    };
+    let tree = walk_path(root, &older).unwrap();
+    assert_eq!(list_top_level(&tree), vec!["old.log".to_string()]);
+}
+
+#[test]
+fn type_filters_keep_matching_files_and_prune_the_rest() {
+    use arbor::fs_scan::types::TypeFilter;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::create_dir_all(root.join("proto")).unwrap();
+    fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+    fs::write(root.join("src/notes.md"), b"# notes").unwrap();
+    fs::write(root.join("docs/guide.md"), b"# guide").unwrap();
+    fs::write(root.join("proto/api.proto"), b"syntax").unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        types: TypeFilter {
+            add: vec!["proto:*.proto".into()],
+            select: vec!["rust".into(), "proto".into()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+    assert_eq!(
+        list_top_level(&tree),
+        vec!["proto".to_string(), "src".to_string()]
+    );
+    let src = find_child(&tree, "src").unwrap();
+    assert_eq!(list_top_level(src), vec!["main.rs".to_string()]);
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        types: TypeFilter {
+            negate: vec!["markdown".into()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+    assert!(find_child(&tree, "docs").is_none());
+    assert!(find_child(&tree, "src").is_some());
+}
+
+#[test]
+fn grep_keeps_matching_files_and_their_ancestors() {
+    use arbor::fs_scan::grep::GrepOptions;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src/deep")).unwrap();
+    fs::create_dir_all(root.join("assets")).unwrap();
+    fs::write(
+        root.join("src/deep/todo.rs"),
+        b"// TODO one\nok\n// FIXME two\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/clean.rs"), b"fn main() {}\n").unwrap();
+    fs::write(root.join("assets/blob.bin"), b"TODO\x00binary").unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        grep: Some(GrepOptions {
+            regex: regex::bytes::Regex::new("TODO|FIXME").unwrap(),
+            lines: true,
+        }),
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+
+    assert_eq!(list_top_level(&tree), vec!["src".to_string()]);
+    let src = find_child(&tree, "src").unwrap();
+    assert_eq!(list_top_level(src), vec!["deep".to_string()]);
+    let todo = find_child(find_child(src, "deep").unwrap(), "todo.rs").unwrap();
+    let m = todo.meta.matches.as_ref().expect("matches");
+    assert_eq!(m.count, 2);
+    assert_eq!(m.lines[1].number, 3);
+    assert_eq!(m.lines[1].text, "// FIXME two");
+}
+
+#[test]
+fn focus_expands_only_the_path_to_targets() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src/renderer")).unwrap();
+    fs::create_dir_all(root.join("src/model")).unwrap();
+    fs::create_dir_all(root.join("docs/img")).unwrap();
+    fs::write(root.join("src/renderer/stdout.rs"), b"").unwrap();
+    fs::write(root.join("src/renderer/json.rs"), b"").unwrap();
+    fs::write(root.join("src/model/node.rs"), b"").unwrap();
+    fs::write(root.join("src/lib.rs"), b"").unwrap();
+    fs::write(root.join("docs/usage.md"), b"").unwrap();
+    fs::write(root.join("docs/img/a.png"), b"").unwrap();
+
+    let opts = WalkOptions {
+        focus: vec!["src/renderer/stdout.rs".into()],
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+
+    let docs = find_child(&tree, "docs").unwrap();
+    assert!(docs.children_slice().is_empty());
+    assert_eq!(docs.meta.collapsed, Some(2));
+
+    let src = find_child(&tree, "src").unwrap();
+    assert_eq!(list_top_level(src), vec!["lib.rs", "model", "renderer"]);
+    assert_eq!(find_child(src, "model").unwrap().meta.collapsed, Some(1));
+
+    let renderer = find_child(src, "renderer").unwrap();
+    assert_eq!(list_top_level(renderer), vec!["json.rs", "stdout.rs"]);
+    let stdout = find_child(renderer, "stdout.rs").unwrap();
+    assert_eq!(stdout.meta.highlight, Some((0, 9)));
+    assert!(
+        find_child(renderer, "json.rs")
+            .unwrap()
+            .meta
+            .highlight
+            .is_none()
+    );
+
+    // With --depth, other branches are expanded down to the limit.
+    let opts = WalkOptions {
+        depth: Some(2),
+        focus: vec!["src/renderer/stdout.rs".into()],
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+    let docs = find_child(&tree, "docs").unwrap();
+    assert_eq!(list_top_level(docs), vec!["img", "usage.md"]);
+    assert_eq!(find_child(docs, "img").unwrap().meta.collapsed, Some(1));
+    let renderer = find_child(find_child(&tree, "src").unwrap(), "renderer").unwrap();
+    assert_eq!(list_top_level(renderer), vec!["json.rs", "stdout.rs"]);
+}
+
+#[test]
+fn tracked_tree_comes_from_the_index() {
+    use arbor::fs_scan::tracked;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    let repo = git2::Repository::init(root).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), b"pub fn f() {}\n").unwrap();
+    fs::write(root.join("build.log"), b"ignored but forced").unwrap();
+    fs::write(root.join("scratch.txt"), b"untracked").unwrap();
+    fs::write(root.join("noise.log"), b"ignored").unwrap();
+    fs::write(root.join(".gitignore"), b"*.log\n").unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("src/lib.rs")).unwrap();
+    index.add_path(Path::new("build.log")).unwrap();
+    index.add_path(Path::new(".gitignore")).unwrap();
+    index.write().unwrap();
+
+    let tree = tracked::read_tree(root, false).unwrap();
+    assert_eq!(
+        list_top_level(&tree),
+        vec![".gitignore", "build.log", "src"]
+    );
+    let lib = find_child(find_child(&tree, "src").unwrap(), "lib.rs").unwrap();
+    assert_eq!(lib.meta.size, Some(14));
+
+    let tree = tracked::read_tree(root, true).unwrap();
+    assert_eq!(
+        list_top_level(&tree),
+        vec![".gitignore", "build.log", "scratch.txt", "src"]
+    );
+
+    let src = tracked::read_tree(&root.join("src"), false).unwrap();
+    assert_eq!(src.name, "src");
+    assert_eq!(list_top_level(&src), vec!["lib.rs"]);
+}
+
+#[test]
+fn hashed_walk_round_trips_through_a_manifest() {
+    use arbor::cli::args::HashAlgo;
+    use arbor::model::node::VerifyState;
+    use arbor::renderer::manifest;
+    use arbor::verify::{Manifest, Tally};
+
+    let (_tmp, root) = make_fs_tree();
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        hash: Some(HashAlgo::Sha256),
+        ..Default::default()
+    };
+    let tree = walk_path(&root, &opts).unwrap();
+    let file = find_child(find_child(&tree, "dir").unwrap(), "sub")
+        .and_then(|sub| find_child(sub, "file.txt"))
+        .unwrap();
+    let digest = file.meta.digest.as_ref().expect("digest");
+    assert_eq!(
+        digest.hex,
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+
+    let mut listing = Vec::new();
+    manifest::render(&mut listing, &tree, &root).unwrap();
+    fs::write(root.join("src/mod.rs"), b"mod other;").unwrap();
+    fs::remove_file(root.join("dir/sub/file.txt")).unwrap();
+
+    let mut tree = walk_path(&root, &opts).unwrap();
+    let mut tally = Tally::default();
+    Manifest::parse(&listing)
+        .unwrap()
+        .verify(&mut tree, &root, &mut tally);
+    assert_eq!(tally.changed, 1);
+    assert_eq!(tally.missing, 1);
+    assert_eq!(tally.new, 0);
+    let gone = find_child(find_child(&tree, "dir").unwrap(), "sub")
+        .and_then(|sub| find_child(sub, "file.txt"))
+        .unwrap();
+    assert_eq!(gone.meta.verify, Some(VerifyState::Missing));
+
+    // Files below `--depth` exist, so they are not reported missing.
+    let opts = WalkOptions {
+        depth: Some(1),
+        ..opts
+    };
+    let mut tree = walk_path(&root, &opts).unwrap();
+    let mut tally = Tally::default();
+    Manifest::parse(&listing)
+        .unwrap()
+        .verify(&mut tree, &root, &mut tally);
+    assert_eq!(tally.changed, 0);
+    assert_eq!(tally.missing, 1);
+}
+
+#[test]
+fn duplicates_keep_only_files_with_copies() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("vendor/a")).unwrap();
+    fs::create_dir_all(root.join("assets")).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("assets/logo.png"), b"PNG data").unwrap();
+    fs::write(root.join("vendor/a/logo.png"), b"PNG data").unwrap();
+    fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+    fs::write(root.join(".hidden.png"), b"PNG data").unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        duplicates: true,
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+
+    assert_eq!(list_top_level(&tree), vec!["assets", "vendor"]);
+    let logo = find_child(find_child(&tree, "assets").unwrap(), "logo.png").unwrap();
+    let d = logo.meta.duplicates.as_ref().expect("duplicates");
+    assert_eq!(d.group, 1);
+    assert_eq!(d.others, vec![b"vendor/a/logo.png".to_vec()]);
+    assert_eq!(d.reclaimable, 8);
+}
+
+#[test]
+fn loc_counts_roll_up_to_directories() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src/cli")).unwrap();
+    fs::write(root.join("src/main.rs"), b"// entry\nfn main() {}\n\n").unwrap();
+    fs::write(root.join("src/cli/args.rs"), b"/* a\n b */\nstruct A;\n").unwrap();
+    fs::write(root.join("src/logo.png"), b"\x89PNG\x00").unwrap();
+
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        loc: true,
+        ..Default::default()
+    };
+    let tree = walk_path(root, &opts).unwrap();
+
+    let src = find_child(&tree, "src").unwrap();
+    let main = find_child(src, "main.rs").unwrap();
+    assert_eq!(main.meta.language, Some("Rust"));
+    let src_loc = src.meta.loc.expect("rolled up counts");
+    assert_eq!((src_loc.code, src_loc.comments, src_loc.blanks), (2, 3, 1));
+    assert_eq!(tree.meta.loc, Some(src_loc));
+    assert_eq!(find_child(src, "logo.png").unwrap().meta.loc, None);
+}
+
+/// Sets the mtime of a file or directory far enough in the past for the
+/// scan cache to keep it.
+fn age(path: &Path) {
+    let then = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+    File::open(path).unwrap().set_modified(then).unwrap();
+}
+
+#[test]
+fn cached_walk_matches_a_fresh_one_and_reuses_unchanged_entries() {
+    let (_tmp, root) = make_fs_tree();
+    let cache_dir = TempDir::new().unwrap();
+    for p in [
+        "src/mod.rs",
+        "src",
+        "dir/sub/file.txt",
+        "dir/sub",
+        "dir",
+        ".gitignore",
+        ".",
+    ] {
+        age(&root.join(p));
+    }
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        hash: Some(HashAlgo::Blake3),
+        ..Default::default()
+    };
+    let cached = |opts: &WalkOptions| {
+        let mut cache = ScanCache::load(cache_dir.path(), &root, opts);
+        let tree = walk_path_cached(&root, opts, &mut cache).unwrap();
+        cache.save().unwrap();
+        tree
+    };
+
+    let fresh = walk_path(&root, &opts).unwrap();
+    assert_eq!(cached(&opts), fresh);
+    assert_eq!(cached(&opts), fresh);
+
+    // Same size and mtime: the cached digest and listing are trusted.
+    fs::write(root.join("src/mod.rs"), b"mod main;").unwrap();
+    fs::write(root.join("src/new.rs"), b"").unwrap();
+    age(&root.join("src/mod.rs"));
+    age(&root.join("src"));
+    assert_eq!(cached(&opts), fresh);
+
+    // Editing the ignore rules lists the tree again, whatever the mtimes.
+    fs::write(root.join(".gitignore"), b"/target\n/dir\n").unwrap();
+    age(&root.join(".gitignore"));
+    let mut cache = ScanCache::load(cache_dir.path(), &root, &opts);
+    let tree = walk_path_cached(&root, &opts, &mut cache).unwrap();
+    assert!(find_child(&tree, "dir").is_none());
+    let src = find_child(&tree, "src").unwrap();
+    assert!(find_child(src, "new.rs").is_some());
+    let digest = &find_child(src, "mod.rs").unwrap().meta.digest;
+    assert_eq!(
+        digest,
+        &find_child(find_child(&fresh, "src").unwrap(), "mod.rs")
+            .unwrap()
+            .meta
+            .digest
+    );
+}
+
+#[test]
+fn diff_json_keeps_the_root_name_of_the_new_side() {
+    let tmp = TempDir::new().unwrap();
+    let (old, new) = (tmp.path().join("old"), tmp.path().join("new"));
+    fs::create_dir(&old).unwrap();
+    fs::create_dir(&new).unwrap();
+    fs::write(old.join("f"), b"a").unwrap();
+    fs::write(new.join("f"), b"bb").unwrap();
+
+    let args = Args::try_parse_from([
+        "arbor".as_ref(),
+        "--json".as_ref(),
+        "diff".as_ref(),
+        old.as_os_str(),
+        new.as_os_str(),
+    ])
+    .unwrap();
+    let config = AppConfig::from_raw(args).unwrap();
+    let mut out = Vec::new();
+    diff::run(&mut out, config.runtime.diff.as_ref().unwrap(), &config).unwrap();
+    let tree = snapshot::parse(&out).unwrap();
+    let walked = walk_path(&new, &WalkOptions::default()).unwrap();
+    assert_eq!(tree.name, walked.name);
+}
+
+#[test]
+fn diff_against_a_json_snapshot_finds_what_changed_since() {
+    let (_tmp, root) = make_fs_tree();
+    let opts = WalkOptions {
+        follow_gitignore: true,
+        hash: Some(HashAlgo::Sha256),
+        ..Default::default()
+    };
+    let mut saved = Vec::new();
+    json::render(&mut saved, &walk_path(&root, &opts).unwrap()).unwrap();
+    let before = snapshot::parse(&saved).unwrap();
+
+    fs::write(root.join("src/mod.rs"), b"mod other;").unwrap();
+    fs::remove_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("README.md"), b"# hi").unwrap();
+
+    let mut tally = diff::Tally::default();
+    let tree = diff::diff(before, walk_path(&root, &opts).unwrap(), &mut tally);
+    assert_eq!(
+        tally,
+        diff::Tally {
+            added: 1,
+            removed: 1,
+            modified: 1,
+            unchanged: 0
+        }
+    );
+    let change = |path: &[&str]| {
+        let mut node = &tree;
+        for name in path {
+            node = find_child(node, name).unwrap();
+        }
+        node.meta.change
+    };
+    assert_eq!(change(&["README.md"]), Some(Change::Added));
+    assert_eq!(change(&["dir"]), Some(Change::Removed));
+    assert_eq!(change(&["dir", "sub", "file.txt"]), Some(Change::Removed));
+    assert_eq!(change(&["src", "mod.rs"]), Some(Change::Modified));
+}